@@ -1,15 +1,26 @@
-use crate::cli::output::OutputFormatter;
-use crate::error::{CudaMgrError, CudaMgrResult};
+use crate::cli::interactive::Interactive;
+use crate::cli::output::{OutputFormatter, TerminalProgress};
+use crate::config::CudaMgrConfig;
+use crate::error::{CudaMgrError, CudaMgrResult, VersionError};
 use async_trait::async_trait;
 use clap::Subcommand;
 
-use crate::install::downloader::PackageDownloader;
-use crate::install::redist;
-use crate::install::{DefaultInstaller, Installer};
+use crate::install::cache::{self, ArchiveCache};
+use crate::install::companion::{self, CompanionLibrary};
+use crate::install::downloader::{DownloadJob, PackageDownloader};
+use crate::install::lock::{self, LockFile};
+use crate::install::preview::PlanPreview;
+use crate::install::receipt::{self, InstallReceipt};
+use crate::install::validator::{InstallationValidator, ValidationReport};
+use crate::install::{dedupe, repair, usage};
+use crate::install::{http, redist};
+use crate::install::{
+    select_components, DefaultInstaller, InstallOptions, InstallationPlan, Installer,
+};
 use crate::system::compatibility::{CompatibilityRegistry, REGISTRY};
 use crate::system::cuda::CudaInstallation;
 use crate::version::registry::VersionRegistry;
-use crate::version::VersionInfo;
+use crate::version::{CompanionInfo, VersionInfo};
 use chrono::Utc;
 
 #[derive(Subcommand)]
@@ -28,6 +39,22 @@ pub enum Command {
     Uninstall(UninstallArgs),
     /// View logs and debugging information
     Logs(LogsArgs),
+    /// Inspect or clean the downloaded archive cache
+    Cache(CacheArgs),
+    /// Share identical files between installed versions to save disk space
+    Dedupe(DedupeArgs),
+    /// Check that installed versions are complete and nvcc works
+    Verify(VerifyArgs),
+    /// Show which installed component a file came from
+    Owns(OwnsArgs),
+    /// Restore missing or modified files of an installed version
+    Repair(RepairArgs),
+    /// Add a companion library (cuDNN, NCCL, cuTENSOR, TensorRT) to an installed toolkit
+    Add(AddArgs),
+    /// Show disk used by installed versions, their components and the archive cache
+    Du(DuArgs),
+    /// Pin a version's release, platform and component archives in cudamgr.lock
+    Lock(LockArgs),
 }
 
 #[derive(clap::Args)]
@@ -58,32 +85,98 @@ pub struct InstallArgs {
     /// Skip driver installation
     #[arg(long)]
     pub skip_driver: bool,
+    /// Only install these redist components (comma-separated, e.g. cuda_nvcc,cuda_cudart)
+    #[arg(long, value_delimiter = ',', value_name = "COMPONENTS")]
+    pub components: Vec<String>,
+    /// Install a named component profile from config (minimal, compiler, runtime, full)
+    #[arg(long, value_name = "PROFILE")]
+    pub profile: Option<String>,
+    /// Install offline from a directory written by 'cudamgr download' (no network access)
+    #[arg(long, value_name = "DIR", conflicts_with = "mirror")]
+    pub from: Option<std::path::PathBuf>,
+    /// Redist root to fetch from instead of NVIDIA (http(s):// or file:// URL)
+    #[arg(long, value_name = "URL")]
+    pub mirror: Option<String>,
+    /// Show the resolved plan, download size and disk space without installing anything
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Print the --dry-run plan as JSON
+    #[arg(long, requires = "dry_run")]
+    pub json: bool,
+    /// Install exactly what ./cudamgr.lock pins and fail on any hash drift
+    #[arg(long, conflicts_with_all = ["components", "profile"])]
+    pub locked: bool,
 }
 
 impl InstallArgs {
     pub fn validate(&self) -> CudaMgrResult<()> {
-        if self.version.is_empty() {
-            return Err(CudaMgrError::Cli("Version cannot be empty".to_string()));
+        validate_version(&self.version)?;
+        validate_components(&self.components)?;
+        validate_source_dir(self.from.as_deref())
+    }
+
+    pub fn install_options(&self) -> InstallOptions {
+        InstallOptions {
+            components: self.components.clone(),
+            profile: self.profile.clone(),
+            source_dir: self.from.clone(),
+            mirror: self.mirror.clone(),
+            platform: None,
+            locked: None,
         }
+    }
+}
+
+/// Version specs are X.Y or X.Y.Z.
+fn validate_version(version: &str) -> CudaMgrResult<()> {
+    if version.is_empty() {
+        return Err(CudaMgrError::Cli("Version cannot be empty".to_string()));
+    }
 
-        // Validate version format (X.Y or X.Y.Z)
-        let parts: Vec<&str> = self.version.split('.').collect();
-        if parts.len() < 2 || parts.len() > 3 {
+    // Validate version format (X.Y or X.Y.Z)
+    let parts: Vec<&str> = version.split('.').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return Err(CudaMgrError::Cli(format!(
+            "Invalid version format '{}'. Expected format: X.Y or X.Y.Z (e.g., 12.0 or 12.0.1)",
+            version
+        )));
+    }
+    for part in &parts {
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
             return Err(CudaMgrError::Cli(format!(
-                "Invalid version format '{}'. Expected format: X.Y or X.Y.Z (e.g., 12.0 or 12.0.1)",
-                self.version
+                "Invalid version format '{}'. Each segment must be a number",
+                version
             )));
         }
-        for part in &parts {
-            if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
-                return Err(CudaMgrError::Cli(format!(
-                    "Invalid version format '{}'. Each segment must be a number",
-                    self.version
-                )));
-            }
+    }
+    Ok(())
+}
+
+/// Component names are manifest keys such as `cuda_nvcc` or `libcublas`.
+fn validate_components(components: &[String]) -> CudaMgrResult<()> {
+    for c in components {
+        if c.is_empty()
+            || !c
+                .chars()
+                .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_')
+        {
+            return Err(CudaMgrError::Cli(format!(
+                "Invalid component name '{}'. Use manifest names like cuda_nvcc or libcublas",
+                c
+            )));
         }
+    }
+    Ok(())
+}
 
-        Ok(())
+/// `--from` must name an existing directory.
+fn validate_source_dir(dir: Option<&std::path::Path>) -> CudaMgrResult<()> {
+    match dir {
+        Some(d) if !d.is_dir() => Err(CudaMgrError::Cli(format!(
+            "--from directory '{}' does not exist",
+            d.display()
+        ))),
+        _ => Ok(()),
     }
 }
 
@@ -94,6 +187,17 @@ pub struct UseArgs {
     /// Install version if not present
     #[arg(short, long)]
     pub install: bool,
+    /// With --install, install offline from a directory written by 'cudamgr download'
+    #[arg(
+        long,
+        value_name = "DIR",
+        requires = "install",
+        conflicts_with = "mirror"
+    )]
+    pub from: Option<std::path::PathBuf>,
+    /// With --install, redist root to fetch from instead of NVIDIA (http(s):// or file:// URL)
+    #[arg(long, value_name = "URL", requires = "install")]
+    pub mirror: Option<String>,
 }
 
 impl UseArgs {
@@ -109,7 +213,7 @@ impl UseArgs {
             ));
         }
 
-        Ok(())
+        validate_source_dir(self.from.as_deref())
     }
 }
 
@@ -143,6 +247,31 @@ pub struct DownloadArgs {
     /// Directory to save downloads (default: cache under cudamgr data dir)
     #[arg(short, long, value_name = "DIR")]
     pub output_dir: Option<std::path::PathBuf>,
+
+    /// Only download these redist components (comma-separated, e.g. cuda_nvcc,cuda_cudart)
+    #[arg(long, value_delimiter = ',', value_name = "COMPONENTS")]
+    pub components: Vec<String>,
+
+    /// Download a named component profile from config (minimal, compiler, runtime, full)
+    #[arg(long, value_name = "PROFILE")]
+    pub profile: Option<String>,
+
+    /// Redist root to fetch from instead of NVIDIA (http(s):// or file:// URL)
+    #[arg(long, value_name = "URL")]
+    pub mirror: Option<String>,
+
+    /// Download archives for another platform (linux-x86_64, linux-sbsa, linux-aarch64,
+    /// linux-ppc64le, windows-x86_64); defaults to this machine's
+    #[arg(long, value_name = "PLATFORM")]
+    pub platform: Option<redist::RedistPlatform>,
+
+    /// Show what would be downloaded and the space it needs without downloading anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Print the --dry-run plans as JSON
+    #[arg(long, requires = "dry_run")]
+    pub json: bool,
 }
 
 impl DownloadArgs {
@@ -160,7 +289,18 @@ impl DownloadArgs {
                 )));
             }
         }
-        Ok(())
+        validate_components(&self.components)
+    }
+
+    pub fn install_options(&self) -> InstallOptions {
+        InstallOptions {
+            components: self.components.clone(),
+            profile: self.profile.clone(),
+            source_dir: None,
+            mirror: self.mirror.clone(),
+            platform: self.platform,
+            locked: None,
+        }
     }
 }
 
@@ -171,6 +311,9 @@ pub struct UninstallArgs {
     /// Skip confirmation prompts
     #[arg(short, long)]
     pub yes: bool,
+    /// Allow removing an install directory outside the configured install_dir
+    #[arg(long)]
+    pub force: bool,
 }
 
 impl UninstallArgs {
@@ -218,6 +361,199 @@ impl LogsArgs {
     }
 }
 
+#[derive(clap::Args)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub action: CacheAction,
+}
+
+#[derive(Subcommand)]
+pub enum CacheAction {
+    /// List cached archives, least recently used first
+    List,
+    /// Show how much disk the cache uses
+    Size,
+    /// Remove cached archives (all of them unless a limit is given)
+    Clean {
+        /// Only remove archives not used for this long (e.g. 30d, 12h, 2w)
+        #[arg(long, value_name = "AGE", value_parser = cache::parse_duration)]
+        older_than: Option<std::time::Duration>,
+        /// Remove least recently used archives until the cache fits (e.g. 20G, 500M)
+        #[arg(long, value_name = "SIZE", value_parser = cache::parse_size)]
+        max_size: Option<u64>,
+    },
+}
+
+impl CacheArgs {
+    pub fn validate(&self) -> CudaMgrResult<()> {
+        Ok(())
+    }
+}
+
+#[derive(clap::Args)]
+pub struct DedupeArgs {
+    /// Use copy-on-write clones instead of hardlinks (Btrfs/XFS on Linux)
+    #[arg(long)]
+    pub reflink: bool,
+    /// Report what would be shared without changing anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+impl DedupeArgs {
+    pub fn validate(&self) -> CudaMgrResult<()> {
+        Ok(())
+    }
+}
+
+#[derive(clap::Args)]
+pub struct VerifyArgs {
+    /// CUDA version to verify (e.g. 12.4 or 12.4.1)
+    pub version: Option<String>,
+    /// Verify every installed version
+    #[arg(long, conflicts_with = "version")]
+    pub all: bool,
+}
+
+impl VerifyArgs {
+    pub fn validate(&self) -> CudaMgrResult<()> {
+        match (&self.version, self.all) {
+            (None, false) => Err(CudaMgrError::Cli(
+                "Specify a version to verify or --all".to_string(),
+            )),
+            (Some(v), _) if v.is_empty() => {
+                Err(CudaMgrError::Cli("Version cannot be empty".to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(clap::Args)]
+pub struct OwnsArgs {
+    /// File inside an install, or a path relative to the install root (e.g. include/cufft.h)
+    pub path: std::path::PathBuf,
+}
+
+impl OwnsArgs {
+    pub fn validate(&self) -> CudaMgrResult<()> {
+        if self.path.as_os_str().is_empty() {
+            return Err(CudaMgrError::Cli("Path cannot be empty".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(clap::Args)]
+pub struct RepairArgs {
+    /// Installed CUDA version to repair
+    pub version: String,
+    /// Take archives from a directory written by 'cudamgr download' (no network access)
+    #[arg(long, value_name = "DIR", conflicts_with = "mirror")]
+    pub from: Option<std::path::PathBuf>,
+    /// Redist root to fetch from instead of NVIDIA (http(s):// or file:// URL)
+    #[arg(long, value_name = "URL")]
+    pub mirror: Option<String>,
+}
+
+impl RepairArgs {
+    pub fn validate(&self) -> CudaMgrResult<()> {
+        if self.version.is_empty() {
+            return Err(CudaMgrError::Cli("Version cannot be empty".to_string()));
+        }
+        validate_source_dir(self.from.as_deref())
+    }
+}
+
+#[derive(clap::Args)]
+pub struct AddArgs {
+    /// Library to add: cudnn, nccl, cutensor or tensorrt
+    pub library: CompanionLibrary,
+    /// Library version (e.g. 9.1 or 9.1.0)
+    pub version: String,
+    /// Installed CUDA version to add it to (defaults to the active version)
+    #[arg(long, value_name = "VERSION")]
+    pub cuda: Option<String>,
+    /// Install into its own directory next to the toolkit instead of merging into it
+    #[arg(long)]
+    pub sibling: bool,
+    /// Redist root to fetch the library from instead of NVIDIA (http(s):// or file:// URL)
+    #[arg(long, value_name = "URL")]
+    pub mirror: Option<String>,
+}
+
+impl AddArgs {
+    pub fn validate(&self) -> CudaMgrResult<()> {
+        if self.version.is_empty() || !self.version.chars().all(|c| c.is_ascii_digit() || c == '.')
+        {
+            return Err(CudaMgrError::Cli(format!(
+                "Invalid version format '{}'. Use format like '9.1' or '9.1.0'",
+                self.version
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(clap::Args)]
+pub struct DuArgs {
+    /// Only report this installed version (e.g. 12.4 or 12.4.1)
+    pub version: Option<String>,
+}
+
+impl DuArgs {
+    pub fn validate(&self) -> CudaMgrResult<()> {
+        if self.version.as_deref() == Some("") {
+            return Err(CudaMgrError::Cli("Version cannot be empty".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(clap::Args)]
+pub struct LockArgs {
+    /// CUDA version to resolve and pin (e.g. 12.4 or 12.4.1)
+    pub version: String,
+    /// Only lock these redist components (comma-separated, e.g. cuda_nvcc,cuda_cudart)
+    #[arg(long, value_delimiter = ',', value_name = "COMPONENTS")]
+    pub components: Vec<String>,
+    /// Lock a named component profile from config (minimal, compiler, runtime, full)
+    #[arg(long, value_name = "PROFILE")]
+    pub profile: Option<String>,
+    /// Resolve from a directory written by 'cudamgr download' (no network access)
+    #[arg(long, value_name = "DIR", conflicts_with = "mirror")]
+    pub from: Option<std::path::PathBuf>,
+    /// Redist root to resolve from instead of NVIDIA (http(s):// or file:// URL)
+    #[arg(long, value_name = "URL")]
+    pub mirror: Option<String>,
+    /// Lock archives for another platform (linux-x86_64, linux-sbsa, linux-aarch64,
+    /// linux-ppc64le, windows-x86_64); defaults to this machine's
+    #[arg(long, value_name = "PLATFORM")]
+    pub platform: Option<redist::RedistPlatform>,
+    /// Where to write the lock
+    #[arg(short, long, value_name = "FILE", default_value = lock::LOCK_FILE)]
+    pub output: std::path::PathBuf,
+}
+
+impl LockArgs {
+    pub fn validate(&self) -> CudaMgrResult<()> {
+        validate_version(&self.version)?;
+        validate_components(&self.components)?;
+        validate_source_dir(self.from.as_deref())
+    }
+
+    pub fn install_options(&self) -> InstallOptions {
+        InstallOptions {
+            components: self.components.clone(),
+            profile: self.profile.clone(),
+            source_dir: self.from.clone(),
+            mirror: self.mirror.clone(),
+            platform: self.platform,
+            locked: None,
+        }
+    }
+}
+
 /// Trait for command handlers that can be executed
 #[async_trait]
 pub trait CommandHandler {
@@ -244,6 +580,14 @@ impl CommandRouter {
             Command::Download(args) => DownloadHandler::new(args).execute().await,
             Command::Uninstall(args) => UninstallHandler::new(args).execute().await,
             Command::Logs(args) => LogsHandler::new(args).execute().await,
+            Command::Cache(args) => CacheHandler::new(args).execute().await,
+            Command::Dedupe(args) => DedupeHandler::new(args).execute().await,
+            Command::Verify(args) => VerifyHandler::new(args).execute().await,
+            Command::Owns(args) => OwnsHandler::new(args).execute().await,
+            Command::Repair(args) => RepairHandler::new(args).execute().await,
+            Command::Add(args) => AddHandler::new(args).execute().await,
+            Command::Du(args) => DuHandler::new(args).execute().await,
+            Command::Lock(args) => LockHandler::new(args).execute().await,
         }
     }
 }
@@ -262,6 +606,14 @@ impl Command {
             Command::Download(args) => args.validate(),
             Command::Uninstall(args) => args.validate(),
             Command::Logs(args) => args.validate(),
+            Command::Cache(args) => args.validate(),
+            Command::Dedupe(args) => args.validate(),
+            Command::Verify(args) => args.validate(),
+            Command::Owns(args) => args.validate(),
+            Command::Repair(args) => args.validate(),
+            Command::Add(args) => args.validate(),
+            Command::Du(args) => args.validate(),
+            Command::Lock(args) => args.validate(),
         }
     }
 }
@@ -464,17 +816,63 @@ impl InstallHandler {
     pub fn new(args: InstallArgs) -> Self {
         Self { args }
     }
+
+    /// Options for the plan, with ./cudamgr.lock loaded for `--locked`.
+    fn install_options(&self) -> CudaMgrResult<InstallOptions> {
+        let mut options = self.args.install_options();
+        if self.args.locked {
+            options.locked = Some(LockFile::load(std::path::Path::new(lock::LOCK_FILE))?);
+        }
+        Ok(options)
+    }
+
+    /// Resolve the plan and show it; nothing is downloaded and the registry is not touched.
+    async fn dry_run(&self) -> CudaMgrResult<()> {
+        let config = CudaMgrConfig::load()?;
+        let installer = DefaultInstaller::with_options(self.install_options()?);
+        let plan = installer.create_plan(&self.args.version).await?;
+        let installed = VersionRegistry::load_or_create()
+            .await?
+            .get_version(&plan.cuda_version)
+            .is_some();
+        let preview = PlanPreview::install(plan, &config.cache_dir)?;
+        if self.args.json {
+            return print_json(&preview);
+        }
+        print_plan_preview(&preview, "install");
+        if installed && !self.args.force {
+            OutputFormatter::warning(&format!(
+                "CUDA {} is already installed; installing it again needs --force",
+                preview.plan.cuda_version
+            ));
+        }
+        println!();
+        OutputFormatter::info("Dry run: nothing was downloaded or installed");
+        Ok(())
+    }
 }
 
 #[async_trait]
 impl CommandHandler for InstallHandler {
     async fn execute(&self) -> CudaMgrResult<()> {
+        if self.args.dry_run {
+            return self.dry_run().await;
+        }
         tracing::info!("Installing CUDA version: {}", self.args.version);
         OutputFormatter::info(&format!("Installing CUDA version {}", self.args.version));
 
         let mut registry = VersionRegistry::load_or_create().await?;
-        let installer = DefaultInstaller;
+        let installer = DefaultInstaller::with_options(self.install_options()?)
+            .with_progress(TerminalProgress::shared());
         let plan = installer.create_plan(&self.args.version).await?;
+        if self.args.locked {
+            OutputFormatter::info(&format!(
+                "Installing CUDA {} exactly as pinned by {} ({} component(s))",
+                plan.cuda_version,
+                lock::LOCK_FILE,
+                plan.artifacts.len()
+            ));
+        }
 
         if registry.get_version(&plan.cuda_version).is_some() && !self.args.force {
             return Err(CudaMgrError::Cli(format!(
@@ -496,26 +894,7 @@ impl CommandHandler for InstallHandler {
         }
 
         installer.execute_plan(&plan).await?;
-
-        // Register the version so list/use can see it
-        let version_info = VersionInfo {
-            version: plan.cuda_version.clone(),
-            install_path: plan.install_path.clone(),
-            is_active: false,
-            install_date: Utc::now(),
-            size_bytes: 0,
-        };
-        if registry.get_version(&plan.cuda_version).is_some() {
-            registry.remove_version(&plan.cuda_version)?;
-        }
-        registry.add_version(version_info);
-        registry.save().await?;
-
-        OutputFormatter::success(&format!(
-            "CUDA {} installed successfully at {}",
-            plan.cuda_version,
-            plan.install_path.display()
-        ));
+        register_and_verify(&mut registry, &plan).await?;
         println!();
         println!("  To use nvcc in your terminal, run:  cudamgr use {}", self.args.version);
         println!("  Then add the shown PATH to your environment (or run the command it prints).");
@@ -523,6 +902,53 @@ impl CommandHandler for InstallHandler {
     }
 }
 
+/// Register a freshly installed plan so list/use can see it, then check it as
+/// 'cudamgr verify' would. Companions in sibling directories survive a reinstall; ones merged
+/// into the old tree went with it.
+async fn register_and_verify(
+    registry: &mut VersionRegistry,
+    plan: &InstallationPlan,
+) -> CudaMgrResult<VersionInfo> {
+    let companions = registry
+        .get_version(&plan.cuda_version)
+        .map(|v| v.companions.clone())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| c.install_path != plan.install_path)
+        .collect();
+    let version_info = VersionInfo {
+        version: plan.cuda_version.clone(),
+        install_path: plan.install_path.clone(),
+        is_active: false,
+        install_date: Utc::now(),
+        size_bytes: CudaInstallation::calculate_install_size(&plan.install_path)?,
+        components: plan.components.clone(),
+        companions,
+    };
+    if registry.get_version(&plan.cuda_version).is_some() {
+        registry.remove_version(&plan.cuda_version)?;
+    }
+    registry.add_version(version_info.clone());
+    registry.save().await?;
+
+    OutputFormatter::success(&format!(
+        "CUDA {} installed successfully at {}",
+        plan.cuda_version,
+        plan.install_path.display()
+    ));
+    let report = InstallationValidator::new()
+        .validate(&plan.install_path)
+        .await?;
+    print_validation_report(&report);
+    if !report.passed() {
+        OutputFormatter::warning(&format!(
+            "CUDA {} installed but failed verification; see above, then re-run 'cudamgr verify {}'",
+            plan.cuda_version, plan.cuda_version
+        ));
+    }
+    Ok(version_info)
+}
+
 pub struct UseHandler {
     args: UseArgs,
 }
@@ -549,19 +975,15 @@ impl CommandHandler for UseHandler {
                         "Version {} not found; installing...",
                         self.args.version
                     ));
-                    let installer = DefaultInstaller;
+                    let installer = DefaultInstaller::with_options(InstallOptions {
+                        source_dir: self.args.from.clone(),
+                        mirror: self.args.mirror.clone(),
+                        ..Default::default()
+                    })
+                    .with_progress(TerminalProgress::shared());
                     let plan = installer.create_plan(&self.args.version).await?;
                     installer.execute_plan(&plan).await?;
-                    let info = VersionInfo {
-                        version: plan.cuda_version.clone(),
-                        install_path: plan.install_path.clone(),
-                        is_active: false,
-                        install_date: Utc::now(),
-                        size_bytes: 0,
-                    };
-                    registry.add_version(info.clone());
-                    registry.save().await?;
-                    info
+                    register_and_verify(&mut registry, &plan).await?
                 } else {
                     return Err(CudaMgrError::Cli(format!(
                         "CUDA version '{}' is not installed. Run 'cudamgr install {}' or 'cudamgr use {} --install' first.",
@@ -606,6 +1028,27 @@ impl CommandHandler for UseHandler {
             println!("    export PATH=\"{}${{PATH:+:$PATH}}\"", bin_path.display());
         }
         println!();
+        for companion in &version_info.companions {
+            println!(
+                "  {}",
+                companion_label(companion, &version_info.install_path)
+            );
+            if companion.install_path != version_info.install_path {
+                let lib = companion
+                    .install_path
+                    .join(if cfg!(windows) { "bin" } else { "lib" });
+                #[cfg(windows)]
+                println!("    $env:PATH = \"{};\" + $env:PATH", lib.display());
+                #[cfg(not(windows))]
+                println!(
+                    "    export LD_LIBRARY_PATH=\"{}${{LD_LIBRARY_PATH:+:$LD_LIBRARY_PATH}}\"",
+                    lib.display()
+                );
+            }
+        }
+        if !version_info.companions.is_empty() {
+            println!();
+        }
         Ok(())
     }
 }
@@ -626,7 +1069,7 @@ impl CommandHandler for ListHandler {
         tracing::info!("Listing CUDA versions, available: {}", self.args.available);
 
         if self.args.available {
-            Self::list_available(&self.args.verbose);
+            Self::list_available(&self.args.verbose).await?;
         } else {
             let registry = VersionRegistry::load_or_create().await?;
             Self::list_installed(&registry, &self.args.verbose)?;
@@ -637,15 +1080,30 @@ impl CommandHandler for ListHandler {
 }
 
 impl ListHandler {
-    /// List CUDA versions that can be installed (from compatibility registry).
-    fn list_available(verbose: &bool) {
+    /// List CUDA versions that can be installed: the full versions in the redist index, or
+    /// the compatibility registry when the index can't be read.
+    async fn list_available(verbose: &bool) -> CudaMgrResult<()> {
+        let config = CudaMgrConfig::load()?;
+        let source = redist::RedistSource::resolve(None, Some(&config))?;
+        let client = http::client(Some(&config))?;
+        match source.list_versions(&client).await {
+            Ok(versions) => {
+                Self::print_published(&versions, verbose);
+                return Ok(());
+            }
+            Err(e) => OutputFormatter::warning(&format!(
+                "Could not read the redist index, showing known versions instead: {}",
+                e
+            )),
+        }
+
         OutputFormatter::section("Available CUDA versions (installable)");
 
         if *verbose {
             let with_driver = REGISTRY.available_cuda_versions_with_min_driver();
             if with_driver.is_empty() {
                 println!("  No version data in registry.");
-                return;
+                return Ok(());
             }
             println!("  {:<12} Min. driver", "Version");
             println!("  {}", "─".repeat(24));
@@ -656,7 +1114,7 @@ impl ListHandler {
             let versions = REGISTRY.available_cuda_versions();
             if versions.is_empty() {
                 println!("  No version data in registry.");
-                return;
+                return Ok(());
             }
             for v in versions {
                 println!("  {}", v);
@@ -664,6 +1122,40 @@ impl ListHandler {
         }
 
         println!("\n  Run 'cudamgr install <version>' to install.");
+        Ok(())
+    }
+
+    /// Print published full versions grouped by minor release, newest first.
+    fn print_published(versions: &[String], verbose: &bool) {
+        OutputFormatter::section("Available CUDA versions (installable)");
+        let min_driver: std::collections::HashMap<String, String> = REGISTRY
+            .available_cuda_versions_with_min_driver()
+            .into_iter()
+            .collect();
+
+        let mut groups: Vec<(String, Vec<&str>)> = Vec::new();
+        for full in versions {
+            let minor = full.rsplit_once('.').map_or(full.as_str(), |(m, _)| m);
+            match groups.last_mut() {
+                Some((m, patches)) if m == minor => patches.push(full),
+                _ => groups.push((minor.to_string(), vec![full])),
+            }
+        }
+
+        if *verbose {
+            println!("  {:<8} {:<12} Releases", "Version", "Min. driver");
+            println!("  {}", "─".repeat(48));
+        }
+        for (minor, patches) in &groups {
+            if *verbose {
+                let driver = min_driver.get(minor).map_or("-", String::as_str);
+                println!("  {:<8} {:<12} {}", minor, driver, patches.join(", "));
+            } else {
+                println!("  {:<8} {}", minor, patches.join(", "));
+            }
+        }
+
+        println!("\n  Run 'cudamgr install <version>' to install (X.Y picks the newest patch).");
     }
 
     /// List CUDA versions currently installed (cudamgr registry + system-detected).
@@ -683,8 +1175,11 @@ impl ListHandler {
                 println!("  {:<10} {:<12} {:<8} Path (cudamgr)", "Version", "Size", "nvcc");
                 println!("  {}", "─".repeat(60));
                 for v in &registry.versions {
-                    let size_gb = v.size_bytes / (1024 * 1024 * 1024);
-                    let size_str = format!("{} GB", size_gb);
+                    // Versions registered before sizes were recorded have none
+                    let size_str = match v.size_bytes {
+                        0 => "-".to_string(),
+                        bytes => OutputFormatter::format_bytes(bytes),
+                    };
                     let nvcc_path = v.install_path.join("bin").join(if cfg!(windows) { "nvcc.exe" } else { "nvcc" });
                     let nvcc_str = if nvcc_path.exists() { "✓" } else { "no" };
                     let active = if v.is_active { " (active)" } else { "" };
@@ -696,6 +1191,12 @@ impl ListHandler {
                         v.install_path.display(),
                         active
                     );
+                    if !v.components.is_empty() {
+                        println!("  {:<10} components: {}", "", v.components.join(", "));
+                    }
+                    for c in &v.companions {
+                        println!("  {:<10} + {}", "", companion_label(c, &v.install_path));
+                    }
                 }
             } else {
                 for v in &registry.versions {
@@ -708,6 +1209,9 @@ impl ListHandler {
                     };
                     let nvcc_note = if nvcc_ok { "" } else { " [no nvcc - reinstall with --force]" };
                     println!("  {}  {}{}{}", v.version, v.install_path.display(), active, nvcc_note);
+                    for c in &v.companions {
+                        println!("    + {}", companion_label(c, &v.install_path));
+                    }
                 }
             }
             if has_detected {
@@ -785,6 +1289,28 @@ impl DownloadHandler {
     pub fn new(args: DownloadArgs) -> Self {
         Self { args }
     }
+
+    /// Resolve each version and show what downloading it into `base_dir` would fetch.
+    async fn dry_run(&self, versions: &[String], base_dir: &std::path::Path) -> CudaMgrResult<()> {
+        let config = CudaMgrConfig::load()?;
+        let installer = DefaultInstaller::with_options(self.args.install_options());
+        let mut previews = Vec::new();
+        for version in versions {
+            let plan = installer.create_plan(version).await?;
+            let version_dir = base_dir.join(&plan.cuda_version);
+            let preview = PlanPreview::download(plan, &version_dir, &config.cache_dir)?;
+            previews.push(preview);
+        }
+        if self.args.json {
+            return print_json(&previews);
+        }
+        for preview in &previews {
+            print_plan_preview(preview, "download");
+        }
+        println!();
+        OutputFormatter::info("Dry run: nothing was downloaded");
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -807,16 +1333,31 @@ impl CommandHandler for DownloadHandler {
                 .unwrap_or(std::path::Path::new("."))
                 .join("downloads")
         });
+        if self.args.dry_run {
+            return self.dry_run(&versions, &base_dir).await;
+        }
 
         std::fs::create_dir_all(&base_dir)
             .map_err(|e| CudaMgrError::Cli(format!("Create output dir: {}", e)))?;
 
-        let downloader = PackageDownloader::new();
-        let client = reqwest::Client::new();
-        let base_url = PackageDownloader::redist_base_url();
+        let config = CudaMgrConfig::load()?;
+        let verify = config.verify_downloads;
+        let concurrency = config.download_concurrency();
+        let options = self.args.install_options();
+        let downloader = PackageDownloader::from_config(Some(&config))?
+            .with_progress(TerminalProgress::shared());
+        let client = downloader.client().clone();
+        let archive_cache = ArchiveCache::new(&config.cache_dir);
+        let source = redist::RedistSource::resolve(options.mirror.as_deref(), Some(&config))?;
+        let platform = match options.platform {
+            Some(p) => p,
+            None => redist::RedistPlatform::detect()?,
+        };
 
         OutputFormatter::section("Downloading CUDA redistributables");
         println!("  Output directory: {}", base_dir.display());
+        println!("  Source: {}", source.root());
+        println!("  Platform: {}", platform);
         println!("  Versions: {}", versions.join(", "));
         println!();
 
@@ -824,49 +1365,188 @@ impl CommandHandler for DownloadHandler {
         let mut failed = Vec::new();
 
         for version in &versions {
-            let (full_version, paths) =
-                match redist::resolve_version_to_redist_paths(version, &client).await {
-                    Ok(x) => x,
-                    Err(e) => {
-                        OutputFormatter::warning(&format!("{}: {}", version, e));
-                        failed.push(version.clone());
-                        continue;
-                    }
-                };
+            let resolved = match source.resolve_manifest(version, platform, &client).await {
+                Ok((full, manifest)) => {
+                    let all = redist::platform_artifacts(&manifest, platform);
+                    select_components(&config, &options, all).map(|a| (full, manifest, a))
+                }
+                Err(e) => Err(e),
+            };
+            let resolved = match resolved {
+                Ok(x) => x,
+                Err(e) => {
+                    OutputFormatter::warning(&format!("{}: {}", version, e));
+                    failed.push(version.clone());
+                    continue;
+                }
+            };
+            let (full_version, manifest, artifacts) = resolved;
 
             let version_dir = base_dir.join(&full_version);
             std::fs::create_dir_all(&version_dir)
                 .map_err(|e| CudaMgrError::Cli(format!("Create version dir: {}", e)))?;
-
-            for rel_path in &paths {
-                let url = format!("{}/{}", base_url, rel_path);
-                let filename = rel_path.rsplit('/').next().unwrap_or(rel_path);
-                let dest = version_dir.join(filename);
-                total_files += 1;
-                print!("  [{}] {} ... ", full_version, filename);
-                std::io::Write::flush(&mut std::io::stdout()).ok();
-                match downloader.download(&url, &dest).await {
-                    Ok(()) => println!("OK"),
-                    Err(e) => {
-                        println!("FAILED: {}", e);
-                        failed.push(version.clone());
+            // Saved next to the archives so 'cudamgr install --from' can work offline.
+            let manifest_path = version_dir.join(redist::manifest_file_name(&full_version));
+            let manifest_body = serde_json::to_string_pretty(&manifest)
+                .map_err(|e| CudaMgrError::Cli(format!("Serialize manifest: {}", e)))?;
+            std::fs::write(&manifest_path, manifest_body).map_err(|e| {
+                CudaMgrError::Cli(format!("Write {}: {}", manifest_path.display(), e))
+            })?;
+
+            let jobs: Vec<DownloadJob> = artifacts
+                .iter()
+                .map(|artifact| DownloadJob {
+                    url: source.artifact_url(&artifact.relative_path),
+                    destination: version_dir.join(artifact.file_name()),
+                    artifact: Some(artifact.clone()),
+                })
+                .collect();
+            let reused: Vec<bool> = jobs
+                .iter()
+                .zip(&artifacts)
+                .map(|(job, artifact)| {
+                    !job.destination.exists() && archive_cache.restore(artifact, &job.destination)
+                })
+                .collect();
+            println!("  CUDA {}:", full_version);
+            let results = downloader.fetch_all(&jobs, concurrency, verify).await;
+
+            // Each file's outcome was already printed by the progress display
+            let mut version_failed = false;
+            for ((job, result), was_cached) in jobs.iter().zip(results).zip(reused) {
+                if result.is_ok() {
+                    total_files += 1;
+                }
+                match result {
+                    Ok(()) if was_cached => {}
+                    Ok(()) => {
+                        if verify {
+                            if let Some(artifact) = &job.artifact {
+                                archive_cache.adopt(&job.destination, artifact);
+                            }
+                        }
                     }
+                    Err(_) => version_failed = true,
                 }
             }
+            if version_failed {
+                failed.push(version.clone());
+            }
         }
 
         println!();
-        OutputFormatter::success(&format!(
-            "Downloaded {} files for {} version(s)",
-            total_files,
-            versions.len().saturating_sub(failed.len())
-        ));
-        if !failed.is_empty() {
-            OutputFormatter::warning(&format!("Some versions had errors: {}", failed.join(", ")));
+        if failed.is_empty() {
+            OutputFormatter::success(&format!(
+                "Downloaded {} files for {} version(s)",
+                total_files,
+                versions.len()
+            ));
+            Ok(())
+        } else {
+            println!(
+                "  Downloaded {} files; {} of {} version(s) complete",
+                total_files,
+                versions.len() - failed.len(),
+                versions.len()
+            );
+            Err(CudaMgrError::Cli(format!(
+                "Download failed for CUDA {}",
+                failed.join(", ")
+            )))
         }
+    }
+}
 
-        Ok(())
+/// Table form of a `--dry-run` preview; `action` is "install" or "download".
+fn print_plan_preview(preview: &PlanPreview, action: &str) {
+    let plan = &preview.plan;
+    OutputFormatter::section(&format!("Dry run: {} CUDA {}", action, plan.cuda_version));
+    if let Some(platform) = plan.platform {
+        println!("  {:<18} {}", "Platform", platform);
+    }
+    println!("  {:<18} {}", "Target", plan.install_path.display());
+    let source = match (&plan.source_dir, plan.artifacts.first()) {
+        (Some(dir), _) => format!("{} (offline)", dir.display()),
+        (None, Some(first)) => plan
+            .download_url
+            .strip_suffix(&first.relative_path)
+            .unwrap_or(&plan.download_url)
+            .to_string(),
+        (None, None) => "-".to_string(),
+    };
+    println!("  {:<18} {}", "Source", source);
+    let driver = match &plan.required_driver {
+        Some(driver) => format!(">= {}", driver),
+        None => "unknown".to_string(),
+    };
+    println!("  {:<18} {}", "Required driver", driver);
+    println!();
+
+    println!(
+        "  {:<28} {:<14} {:>10}  SHA-256",
+        "Component", "Version", "Size"
+    );
+    println!("  {}", "─".repeat(120));
+    for artifact in &plan.artifacts {
+        let reused = preview.reused.contains(&artifact.component);
+        println!(
+            "  {:<28} {:<14} {:>10}  {}{}",
+            artifact.component,
+            artifact.version.as_deref().unwrap_or("-"),
+            artifact
+                .size
+                .map_or("-".to_string(), OutputFormatter::format_bytes),
+            artifact.sha256.as_deref().unwrap_or("-"),
+            if reused { "  (reused)" } else { "" }
+        );
+    }
+    println!("  {}", "─".repeat(120));
+    println!(
+        "  {:<43} {:>10}  {} archive(s)",
+        "Total",
+        OutputFormatter::format_bytes(preview.archive_bytes()),
+        plan.artifacts.len()
+    );
+    let reused = match preview.reused.len() {
+        0 => String::new(),
+        n => format!("  {} reused from disk", n),
+    };
+    println!(
+        "  {:<43} {:>10}{}",
+        "To download",
+        OutputFormatter::format_bytes(preview.download_bytes),
+        reused
+    );
+    if preview.unknown_sizes > 0 {
+        OutputFormatter::warning(&format!(
+            "{} archive(s) have no size in the manifest and are not counted",
+            preview.unknown_sizes
+        ));
+    }
+
+    println!();
+    println!("  Disk space:");
+    for disk in &preview.disk {
+        let paths: Vec<String> = disk.paths.iter().map(|p| p.display().to_string()).collect();
+        println!(
+            "    {} {}: needs {}, {} free",
+            if disk.fits() { "✅" } else { "❌" },
+            paths.join(" and "),
+            OutputFormatter::format_bytes(disk.needed_bytes),
+            OutputFormatter::format_bytes(disk.free_bytes)
+        );
     }
+    if !preview.fits() {
+        OutputFormatter::warning(&format!("Not enough disk space to {} this plan", action));
+    }
+}
+
+/// Pretty-printed JSON on stdout, for `--json`.
+fn print_json<T: serde::Serialize>(value: &T) -> CudaMgrResult<()> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| CudaMgrError::Cli(format!("Serialize output: {}", e)))?;
+    println!("{}", json);
+    Ok(())
 }
 
 pub struct UninstallHandler {
@@ -883,13 +1563,124 @@ impl UninstallHandler {
 impl CommandHandler for UninstallHandler {
     async fn execute(&self) -> CudaMgrResult<()> {
         tracing::info!("Uninstalling CUDA version: {}", self.args.version);
-        OutputFormatter::info(&format!("Uninstalling CUDA version {}", self.args.version));
 
-        // TODO: Implement actual uninstall functionality
-        OutputFormatter::warning("Uninstall command implementation pending");
-        Err(CudaMgrError::Cli(
-            "Uninstall command not yet implemented".to_string(),
-        ))
+        let mut registry = VersionRegistry::load_or_create().await?;
+        let version_info = registry
+            .find_version(&self.args.version)
+            .cloned()
+            .ok_or_else(|| {
+                VersionError::NotFound(format!(
+                    "CUDA {} is not installed by cudamgr (see 'cudamgr list')",
+                    self.args.version
+                ))
+            })?;
+        let install_path = &version_info.install_path;
+
+        let config = CudaMgrConfig::load()?;
+        if !Self::is_within(install_path, &config.install_dir) && !self.args.force {
+            return Err(CudaMgrError::Cli(format!(
+                "{} is outside the cudamgr install directory {}; re-run with --force to remove it anyway",
+                install_path.display(),
+                config.install_dir.display()
+            )));
+        }
+
+        let exists = install_path.exists();
+        // Companion libraries added next to the toolkit go with it
+        let siblings: Vec<&std::path::PathBuf> = version_info
+            .companions
+            .iter()
+            .map(|c| &c.install_path)
+            .filter(|p| *p != install_path && p.exists())
+            .filter(|p| Self::is_within(p, &config.install_dir))
+            .collect();
+
+        // What removing them gives back: symlinks cost only the link, and files hardlinked
+        // into other installs (after dedupe) stay on disk
+        let mut trees = vec![(version_info.version.clone(), install_path.clone())];
+        for sibling in &siblings {
+            trees.push((version_info.version.clone(), (*sibling).clone()));
+        }
+        let removed = trees.len();
+        for other in registry
+            .versions
+            .iter()
+            .filter(|v| v.version != version_info.version)
+        {
+            trees.push((other.version.clone(), other.install_path.clone()));
+            for c in &other.companions {
+                if c.install_path != other.install_path {
+                    trees.push((other.version.clone(), c.install_path.clone()));
+                }
+            }
+        }
+        let size: u64 = usage::measure(&trees)?.trees[..removed]
+            .iter()
+            .map(|t| t.bytes - t.shared_bytes)
+            .sum();
+
+        if !self.args.yes {
+            let prompt = if exists {
+                format!(
+                    "Remove CUDA {} at {} ({})?",
+                    version_info.version,
+                    install_path.display(),
+                    OutputFormatter::format_bytes(size)
+                )
+            } else {
+                format!(
+                    "Remove CUDA {} from the registry (directory {} no longer exists)?",
+                    version_info.version,
+                    install_path.display()
+                )
+            };
+            if !Interactive::confirm(&prompt)? {
+                OutputFormatter::info("Uninstall cancelled");
+                return Ok(());
+            }
+        }
+
+        if exists {
+            std::fs::remove_dir_all(install_path).map_err(|e| {
+                CudaMgrError::Cli(format!(
+                    "Failed to remove {}: {}",
+                    install_path.display(),
+                    e
+                ))
+            })?;
+        }
+        for sibling in &siblings {
+            std::fs::remove_dir_all(sibling).map_err(|e| {
+                CudaMgrError::Cli(format!("Failed to remove {}: {}", sibling.display(), e))
+            })?;
+        }
+
+        let was_active = registry.active_version.as_deref() == Some(version_info.version.as_str());
+        registry.remove_version(&version_info.version)?;
+        registry.save().await?;
+
+        OutputFormatter::success(&format!(
+            "Uninstalled CUDA {} (freed {})",
+            version_info.version,
+            OutputFormatter::format_bytes(size)
+        ));
+        if was_active {
+            OutputFormatter::warning(&format!(
+                "CUDA {} was the active version; remove {} from your PATH or run 'cudamgr use <version>'",
+                version_info.version,
+                install_path.join("bin").display()
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl UninstallHandler {
+    /// Whether `path` lies inside `root` (compared after resolving symlinks when possible).
+    pub(crate) fn is_within(path: &std::path::Path, root: &std::path::Path) -> bool {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        path != root && path.starts_with(&root)
     }
 }
 
@@ -916,3 +1707,581 @@ impl CommandHandler for LogsHandler {
         ))
     }
 }
+
+pub struct CacheHandler {
+    args: CacheArgs,
+}
+
+impl CacheHandler {
+    pub fn new(args: CacheArgs) -> Self {
+        Self { args }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for CacheHandler {
+    async fn execute(&self) -> CudaMgrResult<()> {
+        let config = CudaMgrConfig::load()?;
+        let archive_cache = ArchiveCache::new(&config.cache_dir);
+
+        match &self.args.action {
+            CacheAction::List => {
+                let entries = archive_cache.entries()?;
+                OutputFormatter::section("Cached archives");
+                if entries.is_empty() {
+                    println!("  Cache is empty ({})", archive_cache.root().display());
+                    return Ok(());
+                }
+                println!(
+                    "  {:<12} {:>10}  {:<16} Archive",
+                    "sha256", "Size", "Last used"
+                );
+                println!("  {}", "─".repeat(72));
+                for entry in &entries {
+                    let last_used: chrono::DateTime<chrono::Local> = entry.last_used.into();
+                    println!(
+                        "  {:<12} {:>10}  {:<16} {}{}",
+                        entry.sha256.get(..12).unwrap_or(&entry.sha256),
+                        OutputFormatter::format_bytes(entry.size),
+                        last_used.format("%Y-%m-%d %H:%M"),
+                        entry.file_name,
+                        if entry.complete { "" } else { " (partial)" }
+                    );
+                }
+                let total: u64 = entries.iter().map(|e| e.size).sum();
+                println!(
+                    "\n  {} archive(s), {}",
+                    entries.len(),
+                    OutputFormatter::format_bytes(total)
+                );
+            }
+            CacheAction::Size => {
+                let entries = archive_cache.entries()?;
+                let total: u64 = entries.iter().map(|e| e.size).sum();
+                println!(
+                    "{} in {} archive(s) at {}",
+                    OutputFormatter::format_bytes(total),
+                    entries.len(),
+                    archive_cache.root().display()
+                );
+            }
+            CacheAction::Clean {
+                older_than,
+                max_size,
+            } => {
+                let summary = archive_cache.clean(*older_than, *max_size)?;
+                OutputFormatter::success(&format!(
+                    "Removed {} archive(s), freed {}",
+                    summary.removed,
+                    OutputFormatter::format_bytes(summary.freed_bytes)
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct DedupeHandler {
+    args: DedupeArgs,
+}
+
+impl DedupeHandler {
+    pub fn new(args: DedupeArgs) -> Self {
+        Self { args }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for DedupeHandler {
+    async fn execute(&self) -> CudaMgrResult<()> {
+        let config = CudaMgrConfig::load()?;
+        if !config.install_dir.is_dir() {
+            OutputFormatter::info("No versions installed; nothing to deduplicate");
+            return Ok(());
+        }
+        let options = dedupe::DedupeOptions {
+            mode: if self.args.reflink {
+                dedupe::LinkMode::Reflink
+            } else {
+                dedupe::LinkMode::Hardlink
+            },
+            dry_run: self.args.dry_run,
+            focus: None,
+        };
+
+        OutputFormatter::section("Deduplicating installed versions");
+        println!("  Install directory: {}", config.install_dir.display());
+        let report = dedupe::dedupe(&config.install_dir, &options)?;
+        println!("  Files scanned: {}", report.files_scanned);
+
+        let saved = OutputFormatter::format_bytes(report.bytes_saved);
+        if self.args.dry_run {
+            OutputFormatter::info(&format!(
+                "Would link {} duplicate files, saving {}",
+                report.files_linked, saved
+            ));
+        } else {
+            OutputFormatter::success(&format!(
+                "Linked {} duplicate files, saved {}",
+                report.files_linked, saved
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub struct VerifyHandler {
+    args: VerifyArgs,
+}
+
+impl VerifyHandler {
+    pub fn new(args: VerifyArgs) -> Self {
+        Self { args }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for VerifyHandler {
+    async fn execute(&self) -> CudaMgrResult<()> {
+        let registry = VersionRegistry::load_or_create().await?;
+        let versions: Vec<VersionInfo> = match &self.args.version {
+            Some(version) => {
+                let info = registry.find_version(version).cloned().ok_or_else(|| {
+                    VersionError::NotFound(format!(
+                        "CUDA {} is not installed by cudamgr (see 'cudamgr list')",
+                        version
+                    ))
+                })?;
+                vec![info]
+            }
+            None => registry.versions.clone(),
+        };
+        if versions.is_empty() {
+            OutputFormatter::info("No versions installed; nothing to verify");
+            return Ok(());
+        }
+
+        let validator = InstallationValidator::new();
+        let mut failed = Vec::new();
+        for info in &versions {
+            let report = validator.validate(&info.install_path).await?;
+            print_validation_report(&report);
+            if !report.passed() {
+                failed.push(info.version.clone());
+            }
+        }
+        println!();
+        if failed.is_empty() {
+            OutputFormatter::success(&format!("{} version(s) verified", versions.len()));
+            Ok(())
+        } else {
+            Err(CudaMgrError::Cli(format!(
+                "Verification failed for CUDA {}",
+                failed.join(", ")
+            )))
+        }
+    }
+}
+
+/// Pass/fail table for one install.
+fn print_validation_report(report: &ValidationReport) {
+    OutputFormatter::section(&format!(
+        "Verify CUDA {} ({})",
+        report.cuda_version,
+        report.install_path.display()
+    ));
+    for check in &report.checks {
+        println!(
+            "  {:<14} {}  {}",
+            check.name,
+            check.status.label(),
+            check.detail
+        );
+    }
+}
+
+pub struct OwnsHandler {
+    args: OwnsArgs,
+}
+
+impl OwnsHandler {
+    pub fn new(args: OwnsArgs) -> Self {
+        Self { args }
+    }
+
+    /// `path` relative to `install_path` when it lies inside it, resolving symlinks so a
+    /// library symlink maps to the file it points at.
+    fn relative_to(path: &std::path::Path, install_path: &std::path::Path) -> Option<String> {
+        let path = path.canonicalize().ok()?;
+        let root = install_path.canonicalize().ok()?;
+        path.strip_prefix(&root).ok().map(receipt::relative_string)
+    }
+}
+
+#[async_trait]
+impl CommandHandler for OwnsHandler {
+    async fn execute(&self) -> CudaMgrResult<()> {
+        let registry = VersionRegistry::load_or_create().await?;
+        let path = &self.args.path;
+        // A path inside an install names that install only; a relative one that isn't is
+        // looked up in every install.
+        let inside: Vec<(&VersionInfo, String)> = registry
+            .versions
+            .iter()
+            .filter_map(|v| Self::relative_to(path, &v.install_path).map(|r| (v, r)))
+            .collect();
+        let candidates: Vec<(&VersionInfo, String)> = if !inside.is_empty() {
+            inside
+        } else if path.is_relative() {
+            let relative = receipt::relative_string(path);
+            registry
+                .versions
+                .iter()
+                .map(|v| (v, relative.clone()))
+                .collect()
+        } else {
+            return Err(CudaMgrError::Cli(format!(
+                "{} is not inside any cudamgr install (see 'cudamgr list')",
+                path.display()
+            )));
+        };
+
+        let mut found = 0;
+        let mut without_receipt = Vec::new();
+        for (info, relative) in &candidates {
+            let Some(receipt) = InstallReceipt::load(&info.install_path)? else {
+                without_receipt.push(info.version.as_str());
+                continue;
+            };
+            let Some((file, component)) = receipt.owner(relative) else {
+                continue;
+            };
+            found += 1;
+            let owner = match component.and_then(|c| c.version.as_deref()) {
+                Some(version) => format!("{} {}", file.component, version),
+                None => file.component.clone(),
+            };
+            println!("CUDA {}: {} is owned by {}", info.version, file.path, owner);
+            if let Some(url) = component.and_then(|c| c.source_url.as_deref()) {
+                println!("  from {}", url);
+            }
+        }
+        if found > 0 {
+            return Ok(());
+        }
+        if !without_receipt.is_empty() {
+            OutputFormatter::warning(&format!(
+                "No install receipt for CUDA {}; reinstall to record file ownership",
+                without_receipt.join(", ")
+            ));
+        }
+        Err(CudaMgrError::Cli(format!(
+            "No installed component owns {}",
+            path.display()
+        )))
+    }
+}
+
+pub struct RepairHandler {
+    args: RepairArgs,
+}
+
+impl RepairHandler {
+    pub fn new(args: RepairArgs) -> Self {
+        Self { args }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for RepairHandler {
+    async fn execute(&self) -> CudaMgrResult<()> {
+        let registry = VersionRegistry::load_or_create().await?;
+        let info = registry
+            .find_version(&self.args.version)
+            .cloned()
+            .ok_or_else(|| {
+                VersionError::NotFound(format!(
+                    "CUDA {} is not installed by cudamgr (see 'cudamgr list')",
+                    self.args.version
+                ))
+            })?;
+
+        OutputFormatter::section(&format!("Repairing CUDA {}", info.version));
+        let options = InstallOptions {
+            source_dir: self.args.from.clone(),
+            mirror: self.args.mirror.clone(),
+            ..Default::default()
+        };
+        let report =
+            repair::repair(&info.install_path, &options, &TerminalProgress::shared()).await?;
+        if report.damaged_files.is_empty() {
+            OutputFormatter::success(&format!(
+                "CUDA {} matches its install receipt; nothing to repair",
+                info.version
+            ));
+            return Ok(());
+        }
+        println!(
+            "  {} missing or modified file(s), e.g. {}",
+            report.damaged_files.len(),
+            report.damaged_files[0]
+        );
+        if !report.components.is_empty() {
+            OutputFormatter::success(&format!("Re-extracted {}", report.components.join(", ")));
+        }
+        if !report.skipped.is_empty() {
+            OutputFormatter::warning(&format!(
+                "Left alone, as no manifest cudamgr can find lists them: {} (re-add a library \
+                 with 'cudamgr add', or reinstall with 'cudamgr install {} --force')",
+                report.skipped.join(", "),
+                info.version
+            ));
+        }
+        if !report.still_damaged.is_empty() {
+            OutputFormatter::warning(&format!(
+                "{} file(s) are no longer shipped by their component and stay missing: {}",
+                report.still_damaged.len(),
+                report.still_damaged.join(", ")
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub struct AddHandler {
+    args: AddArgs,
+}
+
+impl AddHandler {
+    pub fn new(args: AddArgs) -> Self {
+        Self { args }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for AddHandler {
+    async fn execute(&self) -> CudaMgrResult<()> {
+        let mut registry = VersionRegistry::load_or_create().await?;
+        let wanted = match (&self.args.cuda, &registry.active_version) {
+            (Some(version), _) | (None, Some(version)) => version.clone(),
+            (None, None) => {
+                return Err(CudaMgrError::Cli(
+                    "No active CUDA version; pass --cuda <version>".to_string(),
+                ))
+            }
+        };
+        let toolkit = registry.find_version(&wanted).cloned().ok_or_else(|| {
+            VersionError::NotFound(format!(
+                "CUDA {} is not installed by cudamgr (see 'cudamgr list')",
+                wanted
+            ))
+        })?;
+
+        let library = self.args.library;
+        let options = companion::CompanionOptions {
+            mirror: self.args.mirror.clone(),
+            sibling: self.args.sibling,
+            platform: None,
+        };
+        let plan = companion::plan(library, &self.args.version, &toolkit, &options).await?;
+        OutputFormatter::info(&format!(
+            "Adding {} {} ({}) to CUDA {} -> {}",
+            library.display_name(),
+            plan.version,
+            plan.cuda_variant,
+            toolkit.version,
+            plan.install_path.display()
+        ));
+        let info = companion::install(&plan, &TerminalProgress::shared()).await?;
+
+        if let Some(entry) = registry
+            .versions
+            .iter_mut()
+            .find(|v| v.version == toolkit.version)
+        {
+            entry.companions.retain(|c| c.name != info.name);
+            entry.companions.push(info.clone());
+            if plan.in_tree() {
+                entry.size_bytes = CudaInstallation::calculate_install_size(&entry.install_path)?;
+            }
+        }
+        registry.save().await?;
+
+        OutputFormatter::success(&format!(
+            "{} {} added to CUDA {}",
+            library.display_name(),
+            info.version,
+            toolkit.version
+        ));
+        if !plan.in_tree() {
+            println!(
+                "  Run 'cudamgr use {}' to see how to put it on the library path.",
+                toolkit.version
+            );
+        }
+        Ok(())
+    }
+}
+
+/// "cuDNN 9.1.0 (cuda12)", plus the directory when it isn't the toolkit's own.
+fn companion_label(companion: &CompanionInfo, toolkit_path: &std::path::Path) -> String {
+    let name = companion
+        .name
+        .parse::<CompanionLibrary>()
+        .map(|l| l.display_name().to_string())
+        .unwrap_or_else(|_| companion.name.clone());
+    let label = format!(
+        "{} {} ({})",
+        name, companion.version, companion.cuda_variant
+    );
+    if companion.install_path == toolkit_path {
+        label
+    } else {
+        format!("{} at {}", label, companion.install_path.display())
+    }
+}
+
+pub struct DuHandler {
+    args: DuArgs,
+}
+
+impl DuHandler {
+    pub fn new(args: DuArgs) -> Self {
+        Self { args }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for DuHandler {
+    async fn execute(&self) -> CudaMgrResult<()> {
+        let config = CudaMgrConfig::load()?;
+        let registry = VersionRegistry::load_or_create().await?;
+        let versions: Vec<VersionInfo> = match &self.args.version {
+            Some(version) => {
+                let info = registry.find_version(version).cloned().ok_or_else(|| {
+                    VersionError::NotFound(format!(
+                        "CUDA {} is not installed by cudamgr (see 'cudamgr list')",
+                        version
+                    ))
+                })?;
+                vec![info]
+            }
+            None => registry.versions.clone(),
+        };
+
+        // Each toolkit, then the companions it keeps in their own directories
+        let mut trees = Vec::new();
+        for info in &versions {
+            trees.push((info.version.clone(), info.install_path.clone()));
+            for c in &info.companions {
+                if c.install_path != info.install_path {
+                    let label = format!("{} + {} {}", info.version, c.name, c.version);
+                    trees.push((label, c.install_path.clone()));
+                }
+            }
+        }
+        let report = usage::measure(&trees)?;
+        let cache_size = ArchiveCache::new(&config.cache_dir).total_size()?;
+
+        OutputFormatter::section("Disk usage");
+        if report.trees.is_empty() {
+            println!("  No versions installed");
+        } else {
+            println!("  {:<24} {:>10} {:>10}  Path", "Version", "Size", "Shared");
+            println!("  {}", "─".repeat(72));
+            for tree in &report.trees {
+                println!(
+                    "  {:<24} {:>10} {:>10}  {}",
+                    tree.label,
+                    OutputFormatter::format_bytes(tree.bytes),
+                    OutputFormatter::format_bytes(tree.shared_bytes),
+                    tree.path.display()
+                );
+            }
+            println!("  {}", "─".repeat(72));
+            println!(
+                "  {:<24} {:>10}",
+                "Installed (on disk)",
+                OutputFormatter::format_bytes(report.on_disk_bytes)
+            );
+            if report.saved_bytes() > 0 {
+                println!(
+                    "  {:<24} {:>10}  shared between versions through hardlinks",
+                    "Saved",
+                    OutputFormatter::format_bytes(report.saved_bytes())
+                );
+            }
+        }
+        println!(
+            "  {:<24} {:>10}  {}",
+            "Archive cache",
+            OutputFormatter::format_bytes(cache_size),
+            config.cache_dir.display()
+        );
+        println!(
+            "  {:<24} {:>10}",
+            "Total",
+            OutputFormatter::format_bytes(report.on_disk_bytes + cache_size)
+        );
+
+        for info in &versions {
+            OutputFormatter::section(&format!("Components of CUDA {}", info.version));
+            match InstallReceipt::load(&info.install_path)? {
+                Some(receipt) => {
+                    for (name, size) in receipt.component_sizes() {
+                        println!("  {:<24} {:>10}", name, OutputFormatter::format_bytes(size));
+                    }
+                }
+                None => println!(
+                    "  No install receipt; reinstall with 'cudamgr install {} --force' to see components",
+                    info.version
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct LockHandler {
+    args: LockArgs,
+}
+
+impl LockHandler {
+    pub fn new(args: LockArgs) -> Self {
+        Self { args }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for LockHandler {
+    async fn execute(&self) -> CudaMgrResult<()> {
+        let installer = DefaultInstaller::with_options(self.args.install_options());
+        let plan = installer.create_plan(&self.args.version).await?;
+        let lock = LockFile::from_plan(&self.args.version, &plan)?;
+        lock.write(&self.args.output)?;
+
+        OutputFormatter::section(&format!(
+            "CUDA {} locked to {} ({})",
+            lock.spec, lock.cuda_version, lock.platform
+        ));
+        for artifact in &lock.components {
+            println!(
+                "  {:<28} {:<14} {}",
+                artifact.component,
+                artifact.version.as_deref().unwrap_or("-"),
+                artifact.sha256.as_deref().unwrap_or("-")
+            );
+        }
+        println!();
+        OutputFormatter::success(&format!(
+            "Wrote {} ({} component(s))",
+            self.args.output.display(),
+            lock.components.len()
+        ));
+        println!(
+            "  Install it on any {} machine with:  cudamgr install {} --locked",
+            lock.platform, lock.spec
+        );
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
-use std::io::{self, Write};
-use std::time::Instant;
+use crate::install::progress::{FileOutcome, Progress, ProgressReporter, Stage};
+use std::io::{self, IsTerminal, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 /// Utility functions for formatted output and progress indicators
 pub struct OutputFormatter;
@@ -75,6 +77,11 @@ impl OutputFormatter {
         println!("{}", "═".repeat(60));
     }
 
+    /// Format a byte count for humans (e.g. "3.2 GB")
+    pub fn format_bytes(bytes: u64) -> String {
+        crate::system::storage::format_bytes(bytes)
+    }
+
     /// Print with indentation
     pub fn indent(level: usize, message: &str) {
         let indent = "  ".repeat(level);
@@ -84,8 +91,11 @@ impl OutputFormatter {
 
 /// Progress bar for long-running operations
 pub struct ProgressBar {
+    /// Expected end position; 0 when unknown
     total: u64,
     current: u64,
+    /// Position the bar started at (a resumed download); excluded from the speed
+    offset: u64,
     message: String,
     start_time: Instant,
 }
@@ -95,37 +105,82 @@ impl ProgressBar {
         Self {
             total,
             current: 0,
+            offset: 0,
             message,
             start_time: Instant::now(),
         }
     }
 
+    /// Start the bar at `offset` instead of 0
+    pub fn with_offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self.current = offset;
+        self
+    }
+
     /// Update progress bar
     pub fn update(&mut self, current: u64) {
+        self.set_position(current);
+        print!("\r  {}", self.render(40));
+        io::stdout().flush().unwrap();
+    }
+
+    /// Move the bar without drawing it
+    pub fn set_position(&mut self, current: u64) {
         self.current = current;
-        let elapsed = self.start_time.elapsed();
+    }
 
-        let percentage = if self.total > 0 {
-            (current as f64 / self.total as f64 * 100.0) as u32
-        } else {
-            0
-        };
+    pub fn position(&self) -> u64 {
+        self.current
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
 
-        let rate = if elapsed.as_secs_f64() > 0.0 {
-            self.current as f64 / elapsed.as_secs_f64()
+    /// Bytes per second since the bar started
+    pub fn rate(&self) -> f64 {
+        let elapsed = self.start_time.elapsed().as_secs_f64();
+        if elapsed > 0.0 {
+            self.current.saturating_sub(self.offset) as f64 / elapsed
         } else {
             0.0
-        };
+        }
+    }
 
-        let bar_width = 40;
-        let filled = (percentage as usize * bar_width / 100).min(bar_width);
-        let bar: String = "█".repeat(filled) + &"░".repeat(bar_width - filled);
+    /// Time left at the current rate, when the total is known
+    pub fn eta(&self) -> Option<Duration> {
+        let rate = self.rate();
+        if self.total == 0 || rate < 1.0 {
+            return None;
+        }
+        let left = self.total.saturating_sub(self.current) as f64;
+        Some(Duration::from_secs_f64(left / rate))
+    }
 
-        print!(
-            "\r  [{}] {}% ({}/{}) {:.0} B/s",
-            bar, percentage, current, self.total, rate
-        );
-        io::stdout().flush().unwrap();
+    /// One line: bar, percentage, bytes, speed and ETA (bytes and speed only when the
+    /// total is unknown)
+    pub fn render(&self, bar_width: usize) -> String {
+        let rate = format!("{}/s", OutputFormatter::format_bytes(self.rate() as u64));
+        if self.total == 0 {
+            return format!("{} {}", OutputFormatter::format_bytes(self.current), rate);
+        }
+        let percentage = (self.current.min(self.total) * 100 / self.total) as usize;
+        let filled = (percentage * bar_width / 100).min(bar_width);
+        let bar: String = "█".repeat(filled) + &"░".repeat(bar_width - filled);
+        let eta = self
+            .eta()
+            .map(|d| format!(" ETA {}", format_duration(d)))
+            .unwrap_or_default();
+        format!(
+            "[{}] {:>3}% {}/{} {}{}",
+            bar,
+            percentage,
+            OutputFormatter::format_bytes(self.current),
+            OutputFormatter::format_bytes(self.total),
+            rate,
+            eta
+        )
     }
 
     pub fn finish(&self) {
@@ -140,6 +195,296 @@ impl ProgressBar {
     pub fn set_message(&mut self, message: String) {
         self.message = message;
     }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start_time.elapsed()
+    }
+}
+
+/// "m:ss", or "h:mm:ss" from an hour up
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Renders download, verification and extraction progress on stdout. On a terminal every
+/// file in flight gets a live bar, with an overall bar for the batch below them; otherwise
+/// (a pipe, a CI log) each file gets one line when it finishes.
+pub struct TerminalProgress {
+    live: bool,
+    state: Mutex<TransferState>,
+}
+
+#[derive(Default)]
+struct TransferState {
+    /// Files in the current batch (0 outside a batch)
+    files: usize,
+    finished: usize,
+    stage: Option<Stage>,
+    /// Bytes of the batch; its position is recomputed from the files on every draw
+    overall: Option<ProgressBar>,
+    /// Bytes of the batch's finished files
+    finished_bytes: u64,
+    active: Vec<(String, ProgressBar)>,
+    /// Lines of the live display currently on screen
+    drawn: usize,
+    last_draw: Option<Instant>,
+}
+
+/// Live bars shown at once; the rest are summarized in one line
+const MAX_LIVE_BARS: usize = 6;
+/// Minimum time between redraws
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+impl TerminalProgress {
+    pub fn new() -> Self {
+        Self {
+            live: io::stdout().is_terminal(),
+            state: Mutex::new(TransferState::default()),
+        }
+    }
+
+    /// A [`Progress`] handle drawing to this terminal
+    pub fn shared() -> Progress {
+        Progress::new(Self::new())
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, TransferState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Print `line` above the live display.
+    fn print_line(&self, state: &mut TransferState, line: &str) {
+        let mut out = io::stdout().lock();
+        if self.live {
+            Self::clear(state, &mut out);
+        }
+        let _ = writeln!(out, "{}", line);
+        if self.live {
+            Self::draw(state, &mut out);
+        }
+        let _ = out.flush();
+    }
+
+    fn redraw(&self, state: &mut TransferState, force: bool) {
+        if !self.live {
+            return;
+        }
+        if !force
+            && state
+                .last_draw
+                .is_some_and(|t| t.elapsed() < REDRAW_INTERVAL)
+        {
+            return;
+        }
+        let mut out = io::stdout().lock();
+        Self::clear(state, &mut out);
+        Self::draw(state, &mut out);
+        let _ = out.flush();
+    }
+
+    fn clear(state: &mut TransferState, out: &mut impl Write) {
+        if state.drawn > 0 {
+            // Up to the first line of the display, then erase to the end of the screen
+            let _ = write!(out, "\x1b[{}A\x1b[J", state.drawn);
+            state.drawn = 0;
+        }
+    }
+
+    fn draw(state: &mut TransferState, out: &mut impl Write) {
+        let width = terminal_width();
+        let mut lines = Vec::new();
+        for (name, bar) in state.active.iter().take(MAX_LIVE_BARS) {
+            lines.push(format!("  {:<32} {}", truncate(name, 32), live_bar(bar)));
+        }
+        if state.active.len() > MAX_LIVE_BARS {
+            lines.push(format!(
+                "  ... and {} more",
+                state.active.len() - MAX_LIVE_BARS
+            ));
+        }
+        let in_flight: u64 = state.active.iter().map(|(_, bar)| bar.position()).sum();
+        let position = state.finished_bytes + in_flight;
+        if let (Some(overall), Some(stage)) = (state.overall.as_mut(), state.stage) {
+            overall.set_position(position);
+            let label = format!(
+                "{} {}/{} files",
+                stage_verb(stage),
+                state.finished,
+                state.files
+            );
+            lines.push(format!("  {:<32} {}", label, live_bar(overall)));
+        }
+        for line in &lines {
+            let _ = writeln!(out, "{}", truncate(line, width.saturating_sub(1)));
+        }
+        state.drawn = lines.len();
+        state.last_draw = Some(Instant::now());
+    }
+}
+
+impl Default for TerminalProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressReporter for TerminalProgress {
+    fn batch_started(&self, stage: Stage, files: usize, total_bytes: Option<u64>) {
+        let mut state = self.state();
+        state.files = files;
+        state.finished = 0;
+        state.finished_bytes = 0;
+        state.stage = Some(stage);
+        state.overall = Some(ProgressBar::new(total_bytes.unwrap_or(0), String::new()));
+        self.redraw(&mut state, true);
+    }
+
+    fn file_started(&self, _stage: Stage, file: &str, total: Option<u64>, offset: u64) {
+        let mut state = self.state();
+        let bar = ProgressBar::new(total.unwrap_or(0), file.to_string()).with_offset(offset);
+        match state.active.iter_mut().find(|(name, _)| name == file) {
+            Some((_, existing)) => *existing = bar,
+            None => state.active.push((file.to_string(), bar)),
+        }
+        self.redraw(&mut state, true);
+    }
+
+    fn file_progress(&self, file: &str, done: u64) {
+        let mut state = self.state();
+        if let Some((_, bar)) = state.active.iter_mut().find(|(name, _)| name == file) {
+            bar.set_position(done);
+        }
+        self.redraw(&mut state, false);
+    }
+
+    fn file_finished(&self, file: &str, outcome: &FileOutcome) {
+        let mut state = self.state();
+        let bar = state
+            .active
+            .iter()
+            .position(|(name, _)| name == file)
+            .map(|i| state.active.remove(i).1);
+        if state.files > 0 {
+            state.finished += 1;
+        }
+        if let Some(bar) = &bar {
+            let bytes = bar.position().max(bar.total());
+            state.finished_bytes += bytes;
+            // Reused files count toward the batch total but not its speed
+            if let (FileOutcome::Reused, Some(overall)) = (outcome, state.overall.as_mut()) {
+                overall.offset += bytes;
+            }
+        }
+        let counter = if state.files > 0 {
+            Some((state.finished, state.files))
+        } else {
+            None
+        };
+        let line = finished_line(counter, file, outcome, bar.as_ref());
+        self.print_line(&mut state, &line);
+    }
+
+    fn batch_finished(&self) {
+        let mut state = self.state();
+        if self.live {
+            let mut out = io::stdout().lock();
+            Self::clear(&mut state, &mut out);
+            let _ = out.flush();
+        }
+        *state = TransferState::default();
+    }
+
+    fn message(&self, text: &str) {
+        let mut state = self.state();
+        self.print_line(&mut state, &format!("  {} ...", text));
+    }
+}
+
+/// The line printed when a file is done, e.g.
+/// "  [3/12] Downloaded cuda_nvcc-linux-x86_64-12.4.131-archive.tar.xz (45.1 MB in 3.2s, 14.1 MB/s)"
+pub fn finished_line(
+    counter: Option<(usize, usize)>,
+    file: &str,
+    outcome: &FileOutcome,
+    bar: Option<&ProgressBar>,
+) -> String {
+    let counter = counter
+        .map(|(done, total)| format!("[{}/{}] ", done, total))
+        .unwrap_or_default();
+    let (verb, detail) = match outcome {
+        FileOutcome::Downloaded => {
+            let detail = bar
+                .map(|b| {
+                    format!(
+                        " ({} in {:.1}s, {}/s)",
+                        OutputFormatter::format_bytes(b.position()),
+                        b.elapsed().as_secs_f64(),
+                        OutputFormatter::format_bytes(b.rate() as u64)
+                    )
+                })
+                .unwrap_or_default();
+            ("Downloaded", detail)
+        }
+        FileOutcome::Reused => ("Reused cached", String::new()),
+        FileOutcome::Verified => ("Verified", String::new()),
+        FileOutcome::Extracted => {
+            let detail = bar
+                .map(|b| format!(" ({})", OutputFormatter::format_bytes(b.total())))
+                .unwrap_or_default();
+            ("Extracted", detail)
+        }
+        FileOutcome::Failed(e) => ("FAILED", format!(": {}", e)),
+    };
+    format!("  {}{} {}{}", counter, verb, file, detail)
+}
+
+/// A bar for the live display; work without byte counts (hash checks) just shows "..."
+fn live_bar(bar: &ProgressBar) -> String {
+    if bar.total() == 0 && bar.position() == 0 {
+        "...".to_string()
+    } else {
+        bar.render(16)
+    }
+}
+
+fn stage_verb(stage: Stage) -> &'static str {
+    match stage {
+        Stage::Download => "Downloading",
+        Stage::Verify => "Verifying",
+        Stage::Extract => "Extracting",
+    }
+}
+
+/// `text` cut to `width` characters, ending in "…" when shortened
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+/// Columns of the terminal on stdout, 80 when it can't be told
+#[cfg(unix)]
+fn terminal_width() -> usize {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let rc = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    if rc == 0 && size.ws_col > 0 {
+        size.ws_col as usize
+    } else {
+        80
+    }
+}
+
+#[cfg(not(unix))]
+fn terminal_width() -> usize {
+    80
 }
 
 /// Spinner for indeterminate progress
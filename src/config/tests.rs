@@ -1,4 +1,5 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use crate::config::types::*;
     use std::path::PathBuf;
@@ -19,6 +20,19 @@ mod tests {
                 https_proxy: Some("https://proxy:8080".to_string()),
                 no_proxy: vec!["localhost".to_string(), "127.0.0.1".to_string()],
             }),
+            profiles: default_profiles(),
+            redist_url: None,
+            network: NetworkConfig {
+                connect_timeout_secs: 10,
+                read_timeout_secs: 60,
+                stall_timeout_secs: 30,
+                index_ttl_secs: 3600,
+                retry: RetryPolicy {
+                    max_attempts: 2,
+                    ..RetryPolicy::default()
+                },
+            },
+            dedupe_after_install: true,
         };
 
         let json = serde_json::to_string_pretty(&config).unwrap();
@@ -42,6 +56,38 @@ mod tests {
         assert!(config.proxy_settings.is_none());
     }
 
+    #[test]
+    fn test_download_concurrency() {
+        let mut config = CudaMgrConfig::default();
+        assert_eq!(config.download_concurrency(), 3);
+
+        config.max_concurrent_downloads = 0;
+        assert_eq!(config.download_concurrency(), 1);
+
+        config.max_concurrent_downloads = 8;
+        config.parallel_downloads = false;
+        assert_eq!(config.download_concurrency(), 1);
+    }
+
+    #[test]
+    fn test_profiles() {
+        let config = CudaMgrConfig::default();
+        for name in ["minimal", "compiler", "runtime", "full"] {
+            assert!(config.profile(name).is_ok(), "missing profile {}", name);
+        }
+        assert!(config
+            .profile("minimal")
+            .unwrap()
+            .contains(&"cuda_nvcc".to_string()));
+        assert!(config.profile("nope").is_err());
+
+        // Configs written before profiles existed still load, with the built-in set.
+        let mut json = serde_json::to_value(&config).unwrap();
+        json.as_object_mut().unwrap().remove("profiles");
+        let loaded: CudaMgrConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.profiles, default_profiles());
+    }
+
     #[test]
     fn test_log_level_serialization() {
         let levels = vec![
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -12,6 +13,18 @@ pub struct CudaMgrConfig {
     pub max_concurrent_downloads: usize,
     pub default_cuda_version: Option<String>,
     pub proxy_settings: Option<ProxyConfig>,
+    /// Named component sets for `--profile` (a "*" entry means every component)
+    #[serde(default = "default_profiles")]
+    pub profiles: BTreeMap<String, Vec<String>>,
+    /// Redist root (http(s):// or file://); NVIDIA's server when unset
+    #[serde(default)]
+    pub redist_url: Option<String>,
+    /// Timeouts for every HTTP client cudamgr creates
+    #[serde(default)]
+    pub network: NetworkConfig,
+    /// Hardlink files a new install shares with other versions (see `cudamgr dedupe`)
+    #[serde(default)]
+    pub dedupe_after_install: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -21,6 +34,96 @@ pub struct ProxyConfig {
     pub no_proxy: Vec<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NetworkConfig {
+    /// Seconds to wait for a TCP/TLS connection
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// Seconds a transfer may go without receiving data
+    #[serde(default = "default_read_timeout_secs")]
+    pub read_timeout_secs: u64,
+    /// Seconds a streaming download may go without a new chunk before it is restarted
+    #[serde(default = "default_stall_timeout_secs")]
+    pub stall_timeout_secs: u64,
+    /// Seconds the cached redist index stays fresh before it is fetched again
+    #[serde(default = "default_index_ttl_secs")]
+    pub index_ttl_secs: u64,
+    /// How failed requests are retried
+    #[serde(default)]
+    pub retry: RetryPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts per request, including the first
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each further retry
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Upper bound for any delay, including one asked for by `Retry-After`
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// HTTP statuses worth retrying; any other error status fails immediately
+    #[serde(default = "default_retry_statuses")]
+    pub retry_statuses: Vec<u16>,
+}
+
+fn default_max_attempts() -> u32 {
+    4
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    500
+}
+
+fn default_max_backoff_ms() -> u64 {
+    30_000
+}
+
+fn default_retry_statuses() -> Vec<u16> {
+    vec![408, 429, 500, 502, 503, 504]
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            retry_statuses: default_retry_statuses(),
+        }
+    }
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    30
+}
+
+fn default_read_timeout_secs() -> u64 {
+    120
+}
+
+fn default_stall_timeout_secs() -> u64 {
+    60
+}
+
+fn default_index_ttl_secs() -> u64 {
+    6 * 60 * 60
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: default_connect_timeout_secs(),
+            read_timeout_secs: default_read_timeout_secs(),
+            stall_timeout_secs: default_stall_timeout_secs(),
+            index_ttl_secs: default_index_ttl_secs(),
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum LogLevel {
     Error,
@@ -30,6 +133,46 @@ pub enum LogLevel {
     Trace,
 }
 
+/// Built-in install profiles. Components missing from a release's manifest are skipped.
+pub fn default_profiles() -> BTreeMap<String, Vec<String>> {
+    let profile = |components: &[&str]| components.iter().map(|c| c.to_string()).collect();
+    BTreeMap::from([
+        (
+            "minimal".to_string(),
+            profile(&["cuda_nvcc", "cuda_cudart", "cuda_cccl"]),
+        ),
+        (
+            "compiler".to_string(),
+            profile(&[
+                "cuda_nvcc",
+                "cuda_cudart",
+                "cuda_cccl",
+                "cuda_nvrtc",
+                "cuda_cuobjdump",
+                "cuda_nvdisasm",
+                "cuda_nvprune",
+                "cuda_cuxxfilt",
+                "libnvjitlink",
+            ]),
+        ),
+        (
+            "runtime".to_string(),
+            profile(&[
+                "cuda_cudart",
+                "cuda_nvrtc",
+                "libcublas",
+                "libcufft",
+                "libcurand",
+                "libcusolver",
+                "libcusparse",
+                "libnpp",
+                "libnvjitlink",
+            ]),
+        ),
+        ("full".to_string(), profile(&["*"])),
+    ])
+}
+
 impl Default for CudaMgrConfig {
     fn default() -> Self {
         let home_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
@@ -45,6 +188,10 @@ impl Default for CudaMgrConfig {
             max_concurrent_downloads: 3,
             default_cuda_version: None,
             proxy_settings: None,
+            profiles: default_profiles(),
+            redist_url: None,
+            network: NetworkConfig::default(),
+            dedupe_after_install: false,
         }
     }
 }
@@ -84,6 +231,30 @@ impl CudaMgrConfig {
         home_dir.join(".cudamgr").join("config.json")
     }
 
+    /// Components of a named install profile
+    pub fn profile(&self, name: &str) -> crate::error::CudaMgrResult<&[String]> {
+        self.profiles
+            .get(name)
+            .map(|p| p.as_slice())
+            .ok_or_else(|| {
+                crate::error::ConfigError::Profile(format!(
+                    "Unknown profile '{}' (available: {})",
+                    name,
+                    self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+                ))
+                .into()
+            })
+    }
+
+    /// Number of archives to download at once (1 when parallel downloads are disabled).
+    pub fn download_concurrency(&self) -> usize {
+        if self.parallel_downloads {
+            self.max_concurrent_downloads.max(1)
+        } else {
+            1
+        }
+    }
+
     pub fn ensure_directories(&self) -> crate::error::CudaMgrResult<()> {
         std::fs::create_dir_all(&self.install_dir)?;
         std::fs::create_dir_all(&self.cache_dir)?;
@@ -82,6 +82,9 @@ pub enum InstallError {
 
     #[error("Package not found: {0}")]
     PackageNotFound(String),
+
+    #[error("Unsafe archive entry: {0}")]
+    UnsafeArchive(String),
 }
 
 /// Version management errors
@@ -117,6 +120,12 @@ pub enum ConfigError {
 
     #[error("Shell configuration failed: {0}")]
     Shell(String),
+
+    #[error("Install profile error: {0}")]
+    Profile(String),
+
+    #[error("Network configuration error: {0}")]
+    Network(String),
 }
 
 /// Convenient result type for CudaMgr operations
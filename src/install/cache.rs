@@ -0,0 +1,375 @@
+//! Content-addressed archive cache.
+//!
+//! Verified archives live at `<cache_dir>/archives/<sha256>/<file name>`, so the same
+//! archive is downloaded once no matter how many prefixes or releases use it. A file's
+//! mtime records when it was last used, which `cudamgr cache clean` ages entries by.
+
+use crate::error::{CudaMgrResult, InstallError};
+use crate::install::redist::RedistArtifact;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Directory under `cache_dir` that holds the cached archives.
+pub const ARCHIVE_DIR: &str = "archives";
+
+/// One cached archive.
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub sha256: String,
+    pub file_name: String,
+    /// The `<sha256>` directory holding the archive
+    pub dir: PathBuf,
+    /// Bytes on disk, including any partial download
+    pub size: u64,
+    /// When the archive was last downloaded or reused
+    pub last_used: SystemTime,
+    /// False while only a `.part` download is present
+    pub complete: bool,
+}
+
+/// What [`ArchiveCache::clean`] removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanSummary {
+    pub removed: usize,
+    pub freed_bytes: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ArchiveCache {
+    root: PathBuf,
+}
+
+impl ArchiveCache {
+    /// The archive cache inside `cache_dir`.
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            root: cache_dir.join(ARCHIVE_DIR),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Where `artifact` is cached. `None` when the manifest has no usable sha256, in which
+    /// case the archive can't be addressed and is not kept.
+    pub fn path_for(&self, artifact: &RedistArtifact) -> Option<PathBuf> {
+        let sha = artifact.sha256.as_deref()?.to_ascii_lowercase();
+        let valid = sha.len() == 64 && sha.bytes().all(|b| b.is_ascii_hexdigit());
+        valid.then(|| self.root.join(sha).join(artifact.file_name()))
+    }
+
+    /// The cached archive for `artifact`, if it is present.
+    pub fn lookup(&self, artifact: &RedistArtifact) -> Option<PathBuf> {
+        self.path_for(artifact).filter(|p| p.is_file())
+    }
+
+    /// Record that a cached archive was just used, so age-based cleaning keeps it.
+    pub fn touch(&self, path: &Path) {
+        if let Err(e) = filetime::set_file_mtime(path, filetime::FileTime::now()) {
+            tracing::debug!("Could not update {}: {}", path.display(), e);
+        }
+    }
+
+    /// Hard-link an archive verified elsewhere (e.g. in a `download` output directory) into
+    /// the cache. Only links are tried: a copy would double the disk used, so on another
+    /// filesystem the archive is simply not cached.
+    pub fn adopt(&self, path: &Path, artifact: &RedistArtifact) {
+        let Some(cached) = self.path_for(artifact) else {
+            return;
+        };
+        if cached.exists() {
+            return;
+        }
+        let linked = cached
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::hard_link(path, &cached));
+        if let Err(e) = linked {
+            tracing::debug!("Not caching {}: {}", path.display(), e);
+        }
+    }
+
+    /// Put the cached copy of `artifact` at `destination`, linked when possible. Returns
+    /// false when the archive isn't cached; the caller still verifies what it gets.
+    pub fn restore(&self, artifact: &RedistArtifact, destination: &Path) -> bool {
+        let Some(cached) = self.lookup(artifact) else {
+            return false;
+        };
+        let restored = std::fs::hard_link(&cached, destination)
+            .or_else(|_| std::fs::copy(&cached, destination).map(|_| ()));
+        match restored {
+            Ok(()) => {
+                self.touch(&cached);
+                true
+            }
+            Err(e) => {
+                tracing::debug!("Could not reuse {}: {}", cached.display(), e);
+                false
+            }
+        }
+    }
+
+    /// Drop the cached copies of `paths` (archive files inside the cache).
+    pub fn remove_archives(&self, paths: &[PathBuf]) -> CudaMgrResult<()> {
+        for path in paths {
+            let Some(dir) = path
+                .parent()
+                .filter(|d| d.parent() == Some(self.root.as_path()))
+            else {
+                continue;
+            };
+            remove_dir(dir)?;
+        }
+        Ok(())
+    }
+
+    /// Every cached archive, least recently used first.
+    pub fn entries(&self) -> CudaMgrResult<Vec<CacheEntry>> {
+        let dirs = match std::fs::read_dir(&self.root) {
+            Ok(dirs) => dirs,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(
+                    InstallError::Cleanup(format!("Read {}: {}", self.root.display(), e)).into(),
+                )
+            }
+        };
+        let mut entries = Vec::new();
+        for dir in dirs.flatten() {
+            let path = dir.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let mut entry = CacheEntry {
+                sha256: dir.file_name().to_string_lossy().into_owned(),
+                file_name: String::new(),
+                dir: path.clone(),
+                size: 0,
+                last_used: SystemTime::UNIX_EPOCH,
+                complete: false,
+            };
+            for file in std::fs::read_dir(&path).into_iter().flatten().flatten() {
+                let Ok(meta) = file.metadata() else {
+                    continue;
+                };
+                let name = file.file_name().to_string_lossy().into_owned();
+                entry.size += meta.len();
+                entry.last_used = entry
+                    .last_used
+                    .max(meta.modified().unwrap_or(entry.last_used));
+                match name.strip_suffix(".part") {
+                    Some(stem) if entry.file_name.is_empty() => entry.file_name = stem.to_string(),
+                    Some(_) => {}
+                    None => {
+                        entry.file_name = name;
+                        entry.complete = true;
+                    }
+                }
+            }
+            entries.push(entry);
+        }
+        entries.sort_by_key(|e| e.last_used);
+        Ok(entries)
+    }
+
+    /// Bytes used by all cached archives.
+    pub fn total_size(&self) -> CudaMgrResult<u64> {
+        Ok(self.entries()?.iter().map(|e| e.size).sum())
+    }
+
+    /// Remove archives not used within `older_than`, then the least recently used ones until
+    /// the cache fits in `max_size`. With neither limit, empties the cache.
+    pub fn clean(
+        &self,
+        older_than: Option<Duration>,
+        max_size: Option<u64>,
+    ) -> CudaMgrResult<CleanSummary> {
+        let entries = self.entries()?;
+        let mut remaining: u64 = entries.iter().map(|e| e.size).sum();
+        let cutoff = older_than.and_then(|age| SystemTime::now().checked_sub(age));
+        let mut summary = CleanSummary::default();
+        for entry in &entries {
+            let expired = match (older_than, max_size) {
+                (None, None) => true,
+                _ => cutoff.is_some_and(|c| entry.last_used < c),
+            };
+            let over_budget = max_size.is_some_and(|max| remaining > max);
+            if !(expired || over_budget) {
+                continue;
+            }
+            remove_dir(&entry.dir)?;
+            remaining -= entry.size;
+            summary.removed += 1;
+            summary.freed_bytes += entry.size;
+        }
+        Ok(summary)
+    }
+}
+
+fn remove_dir(dir: &Path) -> CudaMgrResult<()> {
+    match std::fs::remove_dir_all(dir) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(InstallError::Cleanup(format!("Remove {}: {}", dir.display(), e)).into()),
+    }
+}
+
+/// Parse an age such as `30d`, `12h`, `45m`, `2w` or a plain number of seconds.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (number, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let value: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid age '{}' (expected e.g. 30d, 12h)", s))?;
+    let secs = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "unknown unit '{}' in '{}' (use s, m, h, d, w)",
+                unit, s
+            ))
+        }
+    };
+    Ok(Duration::from_secs(value.saturating_mul(secs)))
+}
+
+/// Parse a size such as `20G`, `500M`, `1.5GB` or a plain number of bytes (binary units).
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let value: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid size '{}' (expected e.g. 20G, 500M)", s))?;
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().trim_end_matches('B') {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => {
+            return Err(format!(
+                "unknown unit '{}' in '{}' (use K, M, G, T)",
+                unit, s
+            ))
+        }
+    };
+    Ok((value * multiplier as f64) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact(sha: &str, file: &str) -> RedistArtifact {
+        RedistArtifact {
+            component: "cuda_cudart".to_string(),
+            version: None,
+            relative_path: format!("cuda_cudart/linux-x86_64/{}", file),
+            sha256: Some(sha.to_string()),
+            size: None,
+        }
+    }
+
+    fn put(cache: &ArchiveCache, sha: char, size: usize, age_secs: u64) -> PathBuf {
+        let path = cache
+            .path_for(&artifact(
+                &sha.to_string().repeat(64),
+                &format!("{}.tar.xz", sha),
+            ))
+            .unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, vec![0u8; size]).unwrap();
+        let when = SystemTime::now() - Duration::from_secs(age_secs);
+        filetime::set_file_mtime(&path, filetime::FileTime::from_system_time(when)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_path_for_requires_a_real_sha256() {
+        let cache = ArchiveCache::new(Path::new("/var/cache/cudamgr"));
+        let sha = "AB".repeat(32);
+        assert_eq!(
+            cache.path_for(&artifact(&sha, "a.tar.xz")),
+            Some(PathBuf::from(format!(
+                "/var/cache/cudamgr/archives/{}/a.tar.xz",
+                "ab".repeat(32)
+            )))
+        );
+        assert!(cache.path_for(&artifact("../../etc", "a.tar.xz")).is_none());
+        assert!(cache
+            .path_for(&RedistArtifact {
+                sha256: None,
+                ..artifact("", "a.tar.xz")
+            })
+            .is_none());
+    }
+
+    #[test]
+    fn test_clean_by_age_and_size() {
+        let dir = std::env::temp_dir().join("cudamgr_archive_cache");
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = ArchiveCache::new(&dir);
+        put(&cache, 'a', 100, 40 * 86400);
+        put(&cache, 'b', 200, 10 * 86400);
+        put(&cache, 'c', 300, 60);
+        std::fs::write(
+            cache.root().join("c".repeat(64)).join("d.tar.xz.part"),
+            [0u8; 5],
+        )
+        .unwrap();
+
+        let entries = cache.entries().unwrap();
+        let names: Vec<&str> = entries.iter().map(|e| e.file_name.as_str()).collect();
+        assert_eq!(names, vec!["a.tar.xz", "b.tar.xz", "c.tar.xz"]);
+        assert!(entries.iter().all(|e| e.complete));
+        assert_eq!(cache.total_size().unwrap(), 605);
+
+        let summary = cache.clean(parse_duration("30d").ok(), None).unwrap();
+        assert_eq!(
+            summary,
+            CleanSummary {
+                removed: 1,
+                freed_bytes: 100
+            }
+        );
+
+        let summary = cache.clean(None, Some(400)).unwrap();
+        assert_eq!(
+            summary,
+            CleanSummary {
+                removed: 1,
+                freed_bytes: 200
+            }
+        );
+        assert_eq!(cache.total_size().unwrap(), 305);
+
+        let summary = cache.clean(None, None).unwrap();
+        assert_eq!(summary.removed, 1);
+        assert!(cache.entries().unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        assert_eq!(parse_duration("30d"), Ok(Duration::from_secs(30 * 86400)));
+        assert_eq!(parse_duration("12h"), Ok(Duration::from_secs(12 * 3600)));
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("3y").is_err());
+
+        assert_eq!(parse_size("500M"), Ok(500 << 20));
+        assert_eq!(parse_size("20GB"), Ok(20 << 30));
+        assert_eq!(parse_size("1.5G"), Ok(3 << 29));
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert!(parse_size("lots").is_err());
+        assert!(parse_size("5P").is_err());
+    }
+}
@@ -1,5 +1,32 @@
 use crate::error::{CudaMgrResult, InstallError};
-use std::path::Path;
+use crate::install::extract::scratch_suffix;
+use std::path::{Path, PathBuf};
+
+/// Directory an install is staged in before it is renamed into place (`.<name>.staging`).
+pub fn staging_path(install_path: &Path) -> PathBuf {
+    sibling_path(install_path, "staging")
+}
+
+/// Where the previous install is parked while a reinstall is committed (`.<name>.backup`).
+pub fn backup_path(install_path: &Path) -> PathBuf {
+    sibling_path(install_path, "backup")
+}
+
+fn sibling_path(install_path: &Path, suffix: &str) -> PathBuf {
+    let name = install_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "install".to_string());
+    install_path.with_file_name(format!(".{}.{}", name, suffix))
+}
+
+fn remove_dir_if_exists(path: &Path) -> CudaMgrResult<()> {
+    match std::fs::remove_dir_all(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(InstallError::Cleanup(format!("Remove {}: {}", path.display(), e)).into()),
+    }
+}
 
 /// Cleanup utilities for failed installations
 pub struct InstallationCleanup;
@@ -10,25 +37,100 @@ impl InstallationCleanup {
         Self
     }
 
-    /// Clean up failed or partial installations
+    /// Clean up failed or partial installations: drop the staging directory and, if a
+    /// reinstall was interrupted after the old tree was parked, put the old tree back.
     pub async fn cleanup_failed_installation(&self, install_path: &Path) -> CudaMgrResult<()> {
-        // TODO: Implement cleanup logic
         tracing::info!("Cleaning up failed installation at {:?}", install_path);
-        Err(InstallError::Cleanup("Installation cleanup not yet implemented".to_string()).into())
+        remove_dir_if_exists(&staging_path(install_path))?;
+
+        let backup = backup_path(install_path);
+        if backup.exists() {
+            if install_path.exists() {
+                // The new tree was committed; the backup is a leftover.
+                remove_dir_if_exists(&backup)?;
+            } else {
+                self.rollback_installation(install_path).await?;
+            }
+        }
+        Ok(())
     }
 
-    /// Rollback installation changes
+    /// Rollback installation changes by restoring the tree parked at [`backup_path`]
     pub async fn rollback_installation(&self, install_path: &Path) -> CudaMgrResult<()> {
-        // TODO: Implement rollback logic
         tracing::info!("Rolling back installation at {:?}", install_path);
-        Err(InstallError::Cleanup("Installation rollback not yet implemented".to_string()).into())
+        let backup = backup_path(install_path);
+        if !backup.exists() {
+            return Ok(());
+        }
+        remove_dir_if_exists(install_path)?;
+        std::fs::rename(&backup, install_path).map_err(|e| {
+            InstallError::Cleanup(format!(
+                "Restore {} from {}: {}",
+                install_path.display(),
+                backup.display(),
+                e
+            ))
+        })?;
+        Ok(())
+    }
+
+    /// Move a fully staged install into place, replacing any existing install.
+    /// If the final rename fails, the previous install is restored.
+    pub async fn commit_installation(&self, install_path: &Path) -> CudaMgrResult<()> {
+        let staging = staging_path(install_path);
+        let backup = backup_path(install_path);
+
+        if install_path.exists() {
+            remove_dir_if_exists(&backup)?;
+            std::fs::rename(install_path, &backup).map_err(|e| {
+                InstallError::Installation(format!(
+                    "Move existing install {} aside: {}",
+                    install_path.display(),
+                    e
+                ))
+            })?;
+        }
+
+        if let Err(e) = std::fs::rename(&staging, install_path) {
+            self.rollback_installation(install_path).await?;
+            return Err(InstallError::Installation(format!(
+                "Move {} into place: {}",
+                staging.display(),
+                e
+            ))
+            .into());
+        }
+
+        if let Err(e) = remove_dir_if_exists(&backup) {
+            tracing::warn!("Could not remove previous install: {}", e);
+        }
+        Ok(())
     }
 
-    /// Remove temporary files
+    /// Remove temporary files: the archive extraction scratch directories this process left
+    /// in `temp_dir` (`*.<pid>_extract`). Other runs sharing the directory keep theirs, and
+    /// downloaded archives and `.part` files are kept so a retry can reuse or resume them.
     pub async fn remove_temp_files(&self, temp_dir: &Path) -> CudaMgrResult<()> {
-        // TODO: Implement temp file cleanup
         tracing::info!("Removing temporary files from {:?}", temp_dir);
-        Err(InstallError::Cleanup("Temp file cleanup not yet implemented".to_string()).into())
+        let entries = match std::fs::read_dir(temp_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => {
+                return Err(
+                    InstallError::Cleanup(format!("Read {}: {}", temp_dir.display(), e)).into(),
+                )
+            }
+        };
+        let suffix = scratch_suffix();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_scratch =
+                path.is_dir() && entry.file_name().to_string_lossy().ends_with(&suffix);
+            if is_scratch {
+                remove_dir_if_exists(&path)?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -37,3 +139,85 @@ impl Default for InstallationCleanup {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fresh_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_staging_and_backup_paths() {
+        let p = Path::new("/opt/cudamgr/versions/12.4.1");
+        assert_eq!(
+            staging_path(p),
+            Path::new("/opt/cudamgr/versions/.12.4.1.staging")
+        );
+        assert_eq!(
+            backup_path(p),
+            Path::new("/opt/cudamgr/versions/.12.4.1.backup")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_commit_replaces_existing_install() {
+        let root = fresh_dir("cudamgr_cleanup_commit");
+        let install = root.join("12.4.1");
+        std::fs::create_dir_all(&install).unwrap();
+        std::fs::write(install.join("old.txt"), "old").unwrap();
+        std::fs::create_dir_all(staging_path(&install)).unwrap();
+        std::fs::write(staging_path(&install).join("new.txt"), "new").unwrap();
+
+        let cleanup = InstallationCleanup::new();
+        cleanup.commit_installation(&install).await.unwrap();
+        assert!(install.join("new.txt").exists());
+        assert!(!install.join("old.txt").exists());
+        assert!(!staging_path(&install).exists());
+        assert!(!backup_path(&install).exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_restores_interrupted_reinstall() {
+        let root = fresh_dir("cudamgr_cleanup_restore");
+        let install = root.join("12.4.1");
+        std::fs::create_dir_all(backup_path(&install)).unwrap();
+        std::fs::write(backup_path(&install).join("old.txt"), "old").unwrap();
+        std::fs::create_dir_all(staging_path(&install)).unwrap();
+
+        let cleanup = InstallationCleanup::new();
+        cleanup.cleanup_failed_installation(&install).await.unwrap();
+        assert!(install.join("old.txt").exists());
+        assert!(!staging_path(&install).exists());
+        assert!(!backup_path(&install).exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_remove_temp_files_keeps_archives() {
+        let root = fresh_dir("cudamgr_cleanup_temp");
+        let own = root.join(format!("cuda_nvcc-archive.tar{}", scratch_suffix()));
+        // Another process extracting into the same cache
+        let other = root.join(format!(
+            "cuda_cudart-archive.tar.{}_extract",
+            std::process::id() + 1
+        ));
+        std::fs::create_dir_all(&own).unwrap();
+        std::fs::create_dir_all(&other).unwrap();
+        std::fs::write(root.join("cuda_nvcc-archive.tar.xz.part"), "x").unwrap();
+
+        InstallationCleanup::new()
+            .remove_temp_files(&root)
+            .await
+            .unwrap();
+        assert!(!own.exists());
+        assert!(other.exists());
+        assert!(root.join("cuda_nvcc-archive.tar.xz.part").exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,650 @@
+//! Companion libraries (cuDNN, NCCL, cuTENSOR, TensorRT) installed against a toolkit.
+//!
+//! NVIDIA publishes these under their own redist roots with the same `redistrib_X.Y.Z.json`
+//! manifests as the toolkit, except that each platform entry holds one archive per CUDA
+//! major (`cuda11`, `cuda12`, ...). The variant matching the target toolkit is installed
+//! into the toolkit's tree, or into a sibling directory next to it.
+
+use crate::config::CudaMgrConfig;
+use crate::error::{CudaMgrError, CudaMgrResult, InstallError};
+use crate::install::extract::{extract_and_merge, merge_tree};
+use crate::install::progress::{Progress, Stage};
+use crate::install::receipt::{ComponentRecord, InstallReceipt, LibraryRelease};
+use crate::install::{cache, cleanup, downloader, http, redist};
+use crate::version::{CompanionInfo, VersionInfo};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const NVIDIA_COMPUTE_URL: &str = "https://developer.download.nvidia.com/compute/";
+
+/// A library cudamgr can add to an installed toolkit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompanionLibrary {
+    Cudnn,
+    Nccl,
+    Cutensor,
+    Tensorrt,
+}
+
+impl CompanionLibrary {
+    pub const ALL: [CompanionLibrary; 4] = [
+        CompanionLibrary::Cudnn,
+        CompanionLibrary::Nccl,
+        CompanionLibrary::Cutensor,
+        CompanionLibrary::Tensorrt,
+    ];
+
+    /// Name on the command line and in the registry, e.g. "cudnn"
+    pub fn key(self) -> &'static str {
+        match self {
+            CompanionLibrary::Cudnn => "cudnn",
+            CompanionLibrary::Nccl => "nccl",
+            CompanionLibrary::Cutensor => "cutensor",
+            CompanionLibrary::Tensorrt => "tensorrt",
+        }
+    }
+
+    /// NVIDIA's spelling, for messages
+    pub fn display_name(self) -> &'static str {
+        match self {
+            CompanionLibrary::Cudnn => "cuDNN",
+            CompanionLibrary::Nccl => "NCCL",
+            CompanionLibrary::Cutensor => "cuTENSOR",
+            CompanionLibrary::Tensorrt => "TensorRT",
+        }
+    }
+
+    /// NVIDIA redist root for the library (`compute/<key>/redist/`).
+    pub fn default_root(self) -> String {
+        format!("{}{}/redist/", NVIDIA_COMPUTE_URL, self.key())
+    }
+}
+
+impl std::fmt::Display for CompanionLibrary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.key())
+    }
+}
+
+impl std::str::FromStr for CompanionLibrary {
+    type Err = CudaMgrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|l| l.key().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                let known: Vec<&str> = Self::ALL.iter().map(|l| l.key()).collect();
+                CudaMgrError::Cli(format!(
+                    "Unknown library '{}' (expected one of: {})",
+                    s,
+                    known.join(", ")
+                ))
+            })
+    }
+}
+
+/// Where and how to add a library.
+#[derive(Debug, Clone, Default)]
+pub struct CompanionOptions {
+    /// Redist root to use instead of NVIDIA's (`--mirror`)
+    pub mirror: Option<String>,
+    /// Install into `<toolkit>-<library>-<version>` instead of the toolkit's tree
+    pub sibling: bool,
+    /// Manifest platform; detected from the host when unset
+    pub platform: Option<redist::RedistPlatform>,
+}
+
+/// A resolved library release for one toolkit.
+#[derive(Debug, Clone)]
+pub struct CompanionPlan {
+    pub library: CompanionLibrary,
+    /// Full library version (e.g. "9.1.0")
+    pub version: String,
+    pub release_label: Option<String>,
+    /// Manifest variant (e.g. "cuda12")
+    pub cuda_variant: String,
+    /// Toolkit the library is added to
+    pub toolkit: VersionInfo,
+    pub artifacts: Vec<redist::RedistArtifact>,
+    pub download_urls: Vec<String>,
+    pub install_path: PathBuf,
+    /// Redist root the manifest was read from
+    pub redist_root: String,
+}
+
+impl CompanionPlan {
+    /// What the receipt records about where the library's components came from.
+    pub fn release(&self) -> LibraryRelease {
+        LibraryRelease {
+            name: self.library.key().to_string(),
+            version: self.version.clone(),
+            cuda_variant: self.cuda_variant.clone(),
+            redist_root: self.redist_root.clone(),
+        }
+    }
+
+    /// Whether the library is merged into the toolkit's own tree.
+    pub fn in_tree(&self) -> bool {
+        self.install_path == self.toolkit.install_path
+    }
+}
+
+/// Resolve `version` of `library` to the newest patch with an archive for the toolkit's CUDA
+/// major on this platform.
+pub async fn plan(
+    library: CompanionLibrary,
+    version: &str,
+    toolkit: &VersionInfo,
+    options: &CompanionOptions,
+) -> CudaMgrResult<CompanionPlan> {
+    let config = CudaMgrConfig::load().ok();
+    let source = source_for(library, options.mirror.as_deref(), config.as_ref())?;
+    let client = http::client(config.as_ref())?;
+    let platform = match options.platform {
+        Some(p) => p,
+        None => redist::RedistPlatform::detect()?,
+    };
+    let major = toolkit.version.split('.').next().unwrap_or_default();
+    let cuda_variant = format!("cuda{}", major);
+
+    let (full_version, manifest) = source
+        .resolve_manifest_for(
+            library.display_name(),
+            version,
+            &format!("{} {}", platform, cuda_variant),
+            &client,
+            |json| !redist::variant_artifacts(json, platform, major).is_empty(),
+        )
+        .await?;
+    let artifacts = redist::variant_artifacts(&manifest, platform, major);
+    let download_urls = artifacts
+        .iter()
+        .map(|a| source.artifact_url(&a.relative_path))
+        .collect();
+    let install_path = if options.sibling {
+        sibling_path(&toolkit.install_path, library, &full_version)
+    } else {
+        toolkit.install_path.clone()
+    };
+
+    Ok(CompanionPlan {
+        library,
+        version: full_version,
+        release_label: redist::release_label(&manifest),
+        cuda_variant,
+        toolkit: toolkit.clone(),
+        artifacts,
+        download_urls,
+        install_path,
+        redist_root: source.root().to_string(),
+    })
+}
+
+/// Download the plan's archives and extract them into a staging directory. A sibling install
+/// is then renamed into place; an in-tree install is merged into the toolkit, replacing the
+/// files an earlier release of the library recorded in the toolkit's receipt. Progress goes
+/// to `progress`.
+pub async fn install(plan: &CompanionPlan, progress: &Progress) -> CudaMgrResult<CompanionInfo> {
+    let config = CudaMgrConfig::load().ok();
+    let downloader = downloader::PackageDownloader::from_config(config.as_ref())?
+        .with_progress(progress.clone());
+    let verify = config.as_ref().map(|c| c.verify_downloads).unwrap_or(true);
+    let auto_cleanup = config.as_ref().map(|c| c.auto_cleanup).unwrap_or(true);
+    let concurrency = config
+        .as_ref()
+        .map(|c| c.download_concurrency())
+        .unwrap_or(1);
+    let cache_dir = config
+        .map(|c| c.cache_dir)
+        .unwrap_or_else(|| plan.toolkit.install_path.join("..").join(".cache"));
+    std::fs::create_dir_all(&cache_dir)
+        .map_err(|e| InstallError::Download(format!("Failed to create cache dir: {}", e)))?;
+    let archive_cache = cache::ArchiveCache::new(&cache_dir);
+
+    let jobs: Vec<downloader::DownloadJob> = plan
+        .artifacts
+        .iter()
+        .zip(&plan.download_urls)
+        .map(|(artifact, url)| downloader::DownloadJob {
+            url: url.clone(),
+            destination: archive_cache
+                .path_for(artifact)
+                .unwrap_or_else(|| cache_dir.join(artifact.file_name())),
+            artifact: Some(artifact.clone()),
+        })
+        .collect();
+    progress.message(&format!(
+        "Downloading {} {} archive(s)",
+        jobs.len(),
+        plan.library.display_name()
+    ));
+    for result in downloader.fetch_all(&jobs, concurrency, verify).await {
+        result?;
+    }
+
+    // Both kinds are extracted into a staging directory first, so an archive that fails to
+    // extract leaves the toolkit, and any earlier sibling, as it was.
+    let cleanup = cleanup::InstallationCleanup::new();
+    let in_tree = plan.in_tree();
+    let staged_path = if in_tree {
+        sibling_path(&plan.toolkit.install_path, plan.library, &plan.version)
+    } else {
+        plan.install_path.clone()
+    };
+    let staging = cleanup::staging_path(&staged_path);
+    if in_tree {
+        let _ = std::fs::remove_dir_all(&staging);
+    } else {
+        cleanup.cleanup_failed_installation(&staged_path).await?;
+    }
+    std::fs::create_dir_all(&staging).map_err(|e| {
+        InstallError::Installation(format!("Failed to create staging directory: {}", e))
+    })?;
+    let staged = extract_into(plan, &jobs, &cache_dir, &staging, &archive_cache, progress);
+    let committed = match staged {
+        Ok(staged) if in_tree => commit_in_tree(plan, &staged_path, &staged).map(|_| staged),
+        Ok(staged) => {
+            let receipt = InstallReceipt::record(
+                &plan.toolkit.version,
+                plan.release_label.clone(),
+                staged.records.clone(),
+                &staging,
+                &staged.placed,
+            );
+            match receipt.and_then(|r| r.write(&staging)) {
+                Ok(()) => cleanup
+                    .commit_installation(&plan.install_path)
+                    .await
+                    .map(|_| staged),
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    };
+    let _ = std::fs::remove_dir_all(&staging);
+    let staged = match committed {
+        Ok(staged) => staged,
+        Err(e) => {
+            if !in_tree {
+                if let Err(cleanup_err) = cleanup
+                    .cleanup_failed_installation(&plan.install_path)
+                    .await
+                {
+                    tracing::warn!("{}", cleanup_err);
+                }
+            }
+            return Err(e);
+        }
+    };
+    if auto_cleanup {
+        if let Err(e) = archive_cache.remove_archives(&staged.cached) {
+            tracing::warn!("{}", e);
+        }
+    }
+
+    Ok(CompanionInfo {
+        name: plan.library.key().to_string(),
+        version: plan.version.clone(),
+        cuda_variant: plan.cuda_variant.clone(),
+        install_path: plan.install_path.clone(),
+    })
+}
+
+/// What [`extract_into`] put in the staging directory.
+struct Staged {
+    records: Vec<ComponentRecord>,
+    /// Files and symlinks placed, by component, relative to the staging directory
+    placed: Vec<(String, PathBuf)>,
+    /// Cached archives used
+    cached: Vec<PathBuf>,
+}
+
+/// Extract the downloaded archives into `staging`.
+fn extract_into(
+    plan: &CompanionPlan,
+    jobs: &[downloader::DownloadJob],
+    cache_dir: &Path,
+    staging: &Path,
+    archive_cache: &cache::ArchiveCache,
+    progress: &Progress,
+) -> CudaMgrResult<Staged> {
+    let mut staged = Staged {
+        records: Vec::new(),
+        placed: Vec::new(),
+        cached: Vec::new(),
+    };
+    let archive_bytes = jobs
+        .iter()
+        .map(|j| {
+            std::fs::metadata(&j.destination)
+                .map(|m| m.len())
+                .unwrap_or(0)
+        })
+        .sum();
+    progress.batch_started(Stage::Extract, jobs.len(), Some(archive_bytes));
+    for job in jobs {
+        let Some(artifact) = &job.artifact else {
+            continue;
+        };
+        let files = extract_and_merge(&job.destination, cache_dir, staging, progress)?;
+        staged
+            .placed
+            .extend(files.into_iter().map(|p| (artifact.component.clone(), p)));
+        staged.records.push(ComponentRecord {
+            name: artifact.component.clone(),
+            version: artifact.version.clone(),
+            source_url: Some(job.url.clone()),
+            library: Some(plan.release()),
+        });
+        if job.destination.starts_with(archive_cache.root()) {
+            archive_cache.touch(&job.destination);
+            staged.cached.push(job.destination.clone());
+        } else {
+            let _ = std::fs::remove_file(&job.destination);
+        }
+    }
+    progress.batch_finished();
+    Ok(staged)
+}
+
+/// Merge a staged library into the toolkit's tree and record it in the toolkit's receipt.
+/// The files an earlier release of the library recorded, and any the new one overwrites,
+/// are parked at [`cleanup::backup_path`] of `staged_path` first, so old sonames don't
+/// linger, and are put back if the merge fails.
+fn commit_in_tree(plan: &CompanionPlan, staged_path: &Path, staged: &Staged) -> CudaMgrResult<()> {
+    let target = &plan.install_path;
+    let staging = cleanup::staging_path(staged_path);
+    let backup = cleanup::backup_path(staged_path);
+    let receipt = InstallReceipt::load(target)?;
+
+    let old_files = receipt.iter().flat_map(|r| {
+        r.files
+            .iter()
+            .filter(|f| plan.artifacts.iter().any(|a| a.component == f.component))
+            .map(|f| PathBuf::from(&f.path))
+    });
+    let mut to_park: Vec<PathBuf> = old_files
+        .chain(staged.placed.iter().map(|(_, p)| p.clone()))
+        .collect();
+    to_park.sort();
+    to_park.dedup();
+
+    let _ = std::fs::remove_dir_all(&backup);
+    let mut parked = Vec::new();
+    let mut result = Ok(());
+    for relative in to_park {
+        let path = target.join(&relative);
+        if !std::fs::symlink_metadata(&path).is_ok_and(|m| !m.is_dir()) {
+            continue;
+        }
+        let parked_path = backup.join(&relative);
+        let moved = parked_path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::rename(&path, &parked_path));
+        if let Err(e) = moved {
+            result =
+                Err(
+                    InstallError::Installation(format!("Move {} aside: {}", path.display(), e))
+                        .into(),
+                );
+            break;
+        }
+        parked.push(relative);
+    }
+    if result.is_ok() {
+        result = merge_tree(&staging, target).map(|_| ());
+    }
+    if let Err(e) = result {
+        // Drop whatever the merge placed and put the parked files back
+        for (_, relative) in &staged.placed {
+            let _ = std::fs::remove_file(target.join(relative));
+        }
+        for relative in &parked {
+            let _ = std::fs::rename(backup.join(relative), target.join(relative));
+        }
+        let _ = std::fs::remove_dir_all(&backup);
+        return Err(e);
+    }
+    let _ = std::fs::remove_dir_all(&backup);
+
+    match receipt {
+        Some(mut receipt) => {
+            receipt.replace_components(target, staged.records.clone(), &staged.placed)?;
+            receipt.write(target)
+        }
+        None => {
+            tracing::warn!(
+                "{} has no install receipt; {} files are not recorded",
+                target.display(),
+                plan.library.display_name()
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Archives of a recorded library release for `platform`, with their URLs, read from the
+/// redist root it was installed from.
+pub async fn release_artifacts(
+    release: &LibraryRelease,
+    platform: redist::RedistPlatform,
+    config: Option<&CudaMgrConfig>,
+    client: &reqwest::Client,
+) -> CudaMgrResult<Vec<(redist::RedistArtifact, String)>> {
+    let mut source = redist::RedistSource::new(&release.redist_root)?;
+    if let Some(c) = config {
+        source = source.with_retry(c.network.retry.clone());
+    }
+    let manifest = source.fetch_manifest(&release.version, client).await?;
+    let major = release.cuda_variant.trim_start_matches("cuda");
+    Ok(redist::variant_artifacts(&manifest, platform, major)
+        .into_iter()
+        .map(|a| {
+            let url = source.artifact_url(&a.relative_path);
+            (a, url)
+        })
+        .collect())
+}
+
+/// Redist source for a library: `--mirror`, else NVIDIA's root for it, with the index cached
+/// next to the toolkit's.
+fn source_for(
+    library: CompanionLibrary,
+    mirror: Option<&str>,
+    config: Option<&CudaMgrConfig>,
+) -> CudaMgrResult<redist::RedistSource> {
+    let source =
+        redist::RedistSource::new(&mirror.map_or_else(|| library.default_root(), String::from))?;
+    Ok(match config {
+        Some(c) => source.with_retry(c.network.retry.clone()).with_index_cache(
+            c.cache_dir
+                .join(format!("{}_{}", library.key(), redist::INDEX_CACHE_FILE)),
+            Duration::from_secs(c.network.index_ttl_secs),
+        ),
+        None => source,
+    })
+}
+
+/// `<install_dir>/<toolkit>-<library>-<version>`, e.g. `12.4.1-cudnn-9.1.0`.
+fn sibling_path(toolkit_path: &Path, library: CompanionLibrary, version: &str) -> PathBuf {
+    let toolkit = toolkit_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    toolkit_path.with_file_name(format!("{}-{}-{}", toolkit, library.key(), version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_library_parse_and_roots() {
+        assert_eq!(
+            "cuDNN".parse::<CompanionLibrary>().unwrap(),
+            CompanionLibrary::Cudnn
+        );
+        assert!("cublas".parse::<CompanionLibrary>().is_err());
+        assert_eq!(
+            CompanionLibrary::Cutensor.default_root(),
+            "https://developer.download.nvidia.com/compute/cutensor/redist/"
+        );
+        assert_eq!(
+            sibling_path(
+                Path::new("/opt/cudamgr/12.4.1"),
+                CompanionLibrary::Nccl,
+                "2.21.5"
+            ),
+            PathBuf::from("/opt/cudamgr/12.4.1-nccl-2.21.5")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plan_picks_variant_for_toolkit_major() {
+        let root = std::env::temp_dir().join("cudamgr_companion");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        let platform = redist::RedistPlatform::detect().unwrap();
+        let entry = |v: &str| serde_json::json!({ "relative_path": format!("cudnn/cudnn-9.1.0.70_{}-archive.tar.xz", v) });
+        for (version, variants) in [
+            ("9.1.0", vec!["cuda11", "cuda12"]),
+            ("9.1.1", vec!["cuda11"]),
+        ] {
+            let mut by_variant = serde_json::Map::new();
+            for v in variants {
+                by_variant.insert(v.to_string(), entry(v));
+            }
+            let manifest = serde_json::json!({
+                "release_label": version,
+                "cudnn": { "version": "9.1.0.70", platform.key(): by_variant },
+            });
+            std::fs::write(
+                root.join(format!("redistrib_{}.json", version)),
+                manifest.to_string(),
+            )
+            .unwrap();
+        }
+
+        let toolkit = VersionInfo {
+            version: "12.4.1".to_string(),
+            install_path: root.join("versions/12.4.1"),
+            is_active: false,
+            install_date: chrono::Utc::now(),
+            size_bytes: 0,
+            components: Vec::new(),
+            companions: Vec::new(),
+        };
+        let options = CompanionOptions {
+            mirror: Some(
+                reqwest::Url::from_directory_path(&root)
+                    .unwrap()
+                    .to_string(),
+            ),
+            ..Default::default()
+        };
+        // 9.1.1 only ships cuda11, so a CUDA 12 toolkit gets 9.1.0
+        let plan = plan(CompanionLibrary::Cudnn, "9.1", &toolkit, &options)
+            .await
+            .unwrap();
+        assert_eq!(plan.version, "9.1.0");
+        assert_eq!(plan.cuda_variant, "cuda12");
+        assert!(plan.download_urls[0].ends_with("_cuda12-archive.tar.xz"));
+        assert!(plan.in_tree());
+
+        let mut old = toolkit.clone();
+        old.version = "10.2.89".to_string();
+        let err = super::plan(CompanionLibrary::Cudnn, "9.1", &old, &options)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("cuda10"), "{}", err);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_in_tree_commit_rolls_back_on_failure() {
+        let root = std::env::temp_dir().join("cudamgr_companion_commit");
+        let _ = std::fs::remove_dir_all(&root);
+        let toolkit_path = root.join("versions/12.4.1");
+        std::fs::create_dir_all(toolkit_path.join("include")).unwrap();
+        std::fs::create_dir_all(toolkit_path.join("lib64")).unwrap();
+        std::fs::write(toolkit_path.join("include/cudnn.h"), "old").unwrap();
+        std::fs::write(toolkit_path.join("lib64/libcudnn.so.8"), "old").unwrap();
+        let placed = vec![
+            ("cudnn".to_string(), PathBuf::from("include/cudnn.h")),
+            ("cudnn".to_string(), PathBuf::from("lib64/libcudnn.so.8")),
+        ];
+        InstallReceipt::record("12.4.1", None, Vec::new(), &toolkit_path, &placed)
+            .unwrap()
+            .write(&toolkit_path)
+            .unwrap();
+
+        let plan = CompanionPlan {
+            library: CompanionLibrary::Cudnn,
+            version: "9.1.0".to_string(),
+            release_label: None,
+            cuda_variant: "cuda12".to_string(),
+            toolkit: VersionInfo {
+                version: "12.4.1".to_string(),
+                install_path: toolkit_path.clone(),
+                is_active: false,
+                install_date: chrono::Utc::now(),
+                size_bytes: 0,
+                components: Vec::new(),
+                companions: Vec::new(),
+            },
+            artifacts: vec![redist::RedistArtifact {
+                component: "cudnn".to_string(),
+                version: Some("9.1.0.70".to_string()),
+                relative_path: "cudnn/cudnn-9.1.0.70_cuda12-archive.tar.xz".to_string(),
+                sha256: None,
+                size: None,
+            }],
+            download_urls: Vec::new(),
+            install_path: toolkit_path.clone(),
+            redist_root: CompanionLibrary::Cudnn.default_root(),
+        };
+        let staged_path = sibling_path(&toolkit_path, plan.library, &plan.version);
+        let staging = cleanup::staging_path(&staged_path);
+        let stage = |escape: bool| {
+            let _ = std::fs::remove_dir_all(&staging);
+            std::fs::create_dir_all(staging.join("include")).unwrap();
+            std::fs::create_dir_all(staging.join("lib64")).unwrap();
+            std::fs::write(staging.join("include/cudnn.h"), "new").unwrap();
+            std::fs::write(staging.join("lib64/libcudnn.so.9"), "new").unwrap();
+            if escape {
+                std::os::unix::fs::symlink("../../../..", staging.join("lib64/escape")).unwrap();
+            }
+        };
+        stage(true);
+        let staged = Staged {
+            records: Vec::new(),
+            placed: vec![
+                ("cudnn".to_string(), PathBuf::from("include/cudnn.h")),
+                ("cudnn".to_string(), PathBuf::from("lib64/libcudnn.so.9")),
+            ],
+            cached: Vec::new(),
+        };
+        let read = |p: &str| std::fs::read_to_string(toolkit_path.join(p)).ok();
+
+        // The merge stops at the escaping link: the toolkit is left as it was
+        assert!(commit_in_tree(&plan, &staged_path, &staged).is_err());
+        assert_eq!(read("include/cudnn.h").as_deref(), Some("old"));
+        assert_eq!(read("lib64/libcudnn.so.8").as_deref(), Some("old"));
+        assert_eq!(read("lib64/libcudnn.so.9"), None);
+        assert!(!cleanup::backup_path(&staged_path).exists());
+
+        stage(false);
+        commit_in_tree(&plan, &staged_path, &staged).unwrap();
+        assert_eq!(read("include/cudnn.h").as_deref(), Some("new"));
+        assert_eq!(read("lib64/libcudnn.so.9").as_deref(), Some("new"));
+        assert_eq!(read("lib64/libcudnn.so.8"), None);
+        let receipt = InstallReceipt::load(&toolkit_path).unwrap().unwrap();
+        assert!(receipt.owner("lib64/libcudnn.so.9").is_some());
+        assert!(receipt.owner("lib64/libcudnn.so.8").is_none());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,318 @@
+//! Share identical files between installed toolkit versions.
+//!
+//! Patch releases carry many byte-identical files (headers, CCCL, docs). Files under
+//! `install_dir` are grouped by size, hashed, and every duplicate is replaced by a hardlink
+//! to (or a reflink clone of) the first copy. Contents never change, so checksums recorded
+//! for each version still match afterwards.
+
+use crate::error::{CudaMgrResult, InstallError};
+use crate::install::downloader::sha256_file;
+use crate::system::cuda::file_id;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// How duplicates are shared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LinkMode {
+    /// One inode for all copies; cheap and works on any local filesystem
+    #[default]
+    Hardlink,
+    /// Copy-on-write clone (Btrfs, XFS); each copy stays an independent file
+    Reflink,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DedupeOptions {
+    pub mode: LinkMode,
+    /// Only report what would be linked
+    pub dry_run: bool,
+    /// Only link a duplicate when it, or the copy it is linked to, is inside this directory
+    /// (e.g. a fresh install)
+    pub focus: Option<PathBuf>,
+}
+
+/// Outcome of a dedupe pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DedupeReport {
+    pub files_scanned: usize,
+    /// Duplicates replaced (or, on a dry run, that would be)
+    pub files_linked: usize,
+    pub bytes_saved: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Candidate {
+    path: PathBuf,
+    size: u64,
+    /// Permission bits; files are only shared when these match
+    mode: u32,
+    /// (device, inode) on Unix, to skip files that are already one file
+    inode: Option<(u64, u64)>,
+}
+
+/// Deduplicate every version directory under `install_dir`. Hidden entries (staging and
+/// backup trees) are skipped, as are symlinks and empty files.
+pub fn dedupe(install_dir: &Path, options: &DedupeOptions) -> CudaMgrResult<DedupeReport> {
+    let mut files = Vec::new();
+    let versions = std::fs::read_dir(install_dir).map_err(|e| {
+        InstallError::Installation(format!("Read {}: {}", install_dir.display(), e))
+    })?;
+    for entry in versions.flatten() {
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+        if is_dir && !hidden {
+            collect_files(&entry.path(), &mut files)?;
+        }
+    }
+
+    let mut report = DedupeReport {
+        files_scanned: files.len(),
+        ..DedupeReport::default()
+    };
+    let mut by_size: BTreeMap<(u64, u32), Vec<Candidate>> = BTreeMap::new();
+    for file in files {
+        by_size
+            .entry((file.size, file.mode))
+            .or_default()
+            .push(file);
+    }
+
+    let in_focus = |c: &Candidate| {
+        options
+            .focus
+            .as_ref()
+            .is_none_or(|focus| c.path.starts_with(focus))
+    };
+    for group in by_size.into_values() {
+        if group.len() < 2 {
+            continue;
+        }
+        if !group.iter().any(in_focus) {
+            continue;
+        }
+        let mut by_hash: HashMap<String, Vec<Candidate>> = HashMap::new();
+        let mut hashed_inodes: HashSet<(u64, u64)> = HashSet::new();
+        for candidate in group {
+            // Hardlinks of an already-hashed inode share its hash
+            if let Some(inode) = candidate.inode {
+                if !hashed_inodes.insert(inode) {
+                    continue;
+                }
+            }
+            let hash = sha256_file(&candidate.path).map_err(|e| {
+                InstallError::Installation(format!("Hash {}: {}", candidate.path.display(), e))
+            })?;
+            by_hash.entry(hash).or_default().push(candidate);
+        }
+        for same in by_hash.into_values() {
+            let Some((keep, duplicates)) = same.split_first() else {
+                continue;
+            };
+            for dup in duplicates {
+                if !in_focus(keep) && !in_focus(dup) {
+                    continue;
+                }
+                if !options.dry_run {
+                    match replace_with_link(&keep.path, &dup.path, options.mode) {
+                        Ok(()) => {}
+                        Err(e) if e.kind() == std::io::ErrorKind::Unsupported => {
+                            return Err(InstallError::Installation(format!(
+                                "{} can't hold reflinks ({}); use hardlinks instead",
+                                install_dir.display(),
+                                e
+                            ))
+                            .into());
+                        }
+                        Err(e) => {
+                            tracing::warn!("Could not link {}: {}", dup.path.display(), e);
+                            continue;
+                        }
+                    }
+                }
+                report.files_linked += 1;
+                report.bytes_saved += dup.size;
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<Candidate>) -> CudaMgrResult<()> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| InstallError::Installation(format!("Read {}: {}", dir.display(), e)))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = std::fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if meta.is_dir() {
+            collect_files(&path, out)?;
+        } else if meta.is_file() && meta.len() > 0 {
+            out.push(Candidate {
+                path,
+                size: meta.len(),
+                mode: file_mode(&meta),
+                inode: file_id(&meta),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn file_mode(meta: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn file_mode(meta: &std::fs::Metadata) -> u32 {
+    meta.permissions().readonly() as u32
+}
+
+/// Swap `dup` for a link to `keep` via a temporary name, so `dup` is never missing.
+fn replace_with_link(keep: &Path, dup: &Path, mode: LinkMode) -> std::io::Result<()> {
+    let name = dup
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let tmp = dup.with_file_name(format!(".{}.cudamgr-dedupe", name));
+    let _ = std::fs::remove_file(&tmp);
+    match mode {
+        LinkMode::Hardlink => std::fs::hard_link(keep, &tmp)?,
+        LinkMode::Reflink => {
+            reflink(keep, &tmp)?;
+            // A clone is a new file: carry over the duplicate's own timestamps
+            if let Ok(meta) = std::fs::metadata(dup) {
+                let mtime = filetime::FileTime::from_last_modification_time(&meta);
+                let _ = filetime::set_file_mtime(&tmp, mtime);
+                let _ = std::fs::set_permissions(&tmp, meta.permissions());
+            }
+        }
+    }
+    std::fs::rename(&tmp, dup).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp);
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn reflink(src: &Path, dst: &Path) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+    // _IOW(0x94, 9, int) from <linux/fs.h>
+    const FICLONE: u64 = 0x4004_9409;
+
+    let src_file = std::fs::File::open(src)?;
+    let dst_file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dst)?;
+    let rc = unsafe { libc::ioctl(dst_file.as_raw_fd(), FICLONE as _, src_file.as_raw_fd()) };
+    if rc != 0 {
+        let err = std::io::Error::last_os_error();
+        drop(dst_file);
+        let _ = std::fs::remove_file(dst);
+        let unsupported = [libc::EOPNOTSUPP, libc::EXDEV, libc::EINVAL, libc::ENOTTY];
+        if err.raw_os_error().is_some_and(|n| unsupported.contains(&n)) {
+            return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, err));
+        }
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reflink(_src: &Path, _dst: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "reflinks are only supported on Linux",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, body: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, body).unwrap();
+    }
+
+    #[test]
+    fn test_dedupe_links_identical_files_across_versions() {
+        let root = std::env::temp_dir().join("cudamgr_dedupe");
+        let _ = std::fs::remove_dir_all(&root);
+        let header = "#define CUDART_VERSION 12040\n".repeat(100);
+        write(&root.join("12.4.0/include/cuda_runtime.h"), &header);
+        write(&root.join("12.4.1/include/cuda_runtime.h"), &header);
+        write(&root.join("12.6.3/include/cuda_runtime.h"), &header);
+        write(&root.join("12.4.0/bin/nvcc"), "nvcc 12.4.0");
+        write(&root.join("12.4.1/bin/nvcc"), "nvcc 12.4.1");
+        write(
+            &root.join(".12.6.3.staging/include/cuda_runtime.h"),
+            &header,
+        );
+
+        let dry = dedupe(
+            &root,
+            &DedupeOptions {
+                dry_run: true,
+                ..DedupeOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(dry.files_scanned, 5);
+        assert_eq!(dry.files_linked, 2);
+        assert_eq!(dry.bytes_saved, 2 * header.len() as u64);
+
+        let report = dedupe(&root, &DedupeOptions::default()).unwrap();
+        assert_eq!(report, dry);
+        for v in ["12.4.1", "12.6.3"] {
+            let path = root.join(v).join("include/cuda_runtime.h");
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), header);
+        }
+        assert_eq!(
+            std::fs::read_to_string(root.join("12.4.1/bin/nvcc")).unwrap(),
+            "nvcc 12.4.1"
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let ino = |v: &str| {
+                std::fs::metadata(root.join(v).join("include/cuda_runtime.h"))
+                    .unwrap()
+                    .ino()
+            };
+            assert_eq!(ino("12.4.0"), ino("12.6.3"));
+            // Already shared files are not counted again
+            let again = dedupe(&root, &DedupeOptions::default()).unwrap();
+            assert_eq!(again.files_linked, 0);
+        }
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_dedupe_focus_skips_unrelated_duplicates() {
+        let root = std::env::temp_dir().join("cudamgr_dedupe_focus");
+        let _ = std::fs::remove_dir_all(&root);
+        write(&root.join("11.8.0/doc/a.txt"), "same doc");
+        write(&root.join("12.0.0/doc/a.txt"), "same doc");
+        write(&root.join("12.4.1/include/b.h"), "same header");
+        write(&root.join("12.6.3/include/b.h"), "same header");
+        // Same size as b.h, so in the same group, but neither copy is in focus
+        write(&root.join("11.8.0/include/c.h"), "diff header");
+        write(&root.join("12.0.0/include/c.h"), "diff header");
+
+        let report = dedupe(
+            &root,
+            &DedupeOptions {
+                focus: Some(root.join("12.6.3")),
+                ..DedupeOptions::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(report.files_linked, 1);
+        assert_eq!(report.bytes_saved, "same header".len() as u64);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -1,91 +1,760 @@
+use crate::config::{CudaMgrConfig, NetworkConfig, RetryPolicy};
 use crate::error::{CudaMgrResult, InstallError};
-use std::io::Write;
-use std::path::Path;
-
-const REDIST_BASE: &str = "https://developer.download.nvidia.com/compute/cuda/redist";
+use crate::install::http;
+use crate::install::progress::{FileOutcome, Progress, Stage};
+use crate::install::redist::{self, RedistArtifact};
+use crate::install::retry::{self, Failure};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
 /// Package downloader with progress tracking
+#[derive(Clone)]
 pub struct PackageDownloader {
     client: reqwest::Client,
+    retry: RetryPolicy,
+    stall_timeout: Duration,
+    progress: Progress,
+}
+
+/// One archive to fetch as part of a batch (see [`PackageDownloader::fetch_all`]).
+#[derive(Debug, Clone)]
+pub struct DownloadJob {
+    pub url: String,
+    pub destination: PathBuf,
+    /// Manifest entry used for verification, when known
+    pub artifact: Option<RedistArtifact>,
+}
+
+impl DownloadJob {
+    /// File name shown in per-file status lines.
+    pub fn file_name(&self) -> String {
+        self.destination
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| self.url.clone())
+    }
+}
+
+/// Name a file is reported under: its file name, or the whole path when it has none.
+pub fn progress_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
 }
 
 impl PackageDownloader {
     /// Create a new package downloader
     pub fn new() -> Self {
+        Self::with_client(http::client(None).unwrap_or_else(|_| reqwest::Client::new()))
+    }
+
+    /// Create a downloader that uses `client` (see [`http::client`]) and the default
+    /// retry policy
+    pub fn with_client(client: reqwest::Client) -> Self {
+        let network = NetworkConfig::default();
         Self {
-            client: reqwest::Client::builder()
-                .user_agent("cudamgr/1.0")
-                .build()
-                .unwrap_or_else(|_| reqwest::Client::new()),
+            client,
+            retry: network.retry,
+            stall_timeout: Duration::from_secs(network.stall_timeout_secs),
+            progress: Progress::default(),
         }
     }
 
-    /// Download a package from URL to destination path with progress
+    /// Create a downloader with the proxy, timeout and retry settings from `config`
+    pub fn from_config(config: Option<&CudaMgrConfig>) -> CudaMgrResult<Self> {
+        let network = config.map(|c| c.network.clone()).unwrap_or_default();
+        Ok(Self {
+            client: http::client(config)?,
+            retry: network.retry,
+            stall_timeout: Duration::from_secs(network.stall_timeout_secs),
+            progress: Progress::default(),
+        })
+    }
+
+    /// Replace the retry policy and stall timeout
+    pub fn with_retry(mut self, retry: RetryPolicy, stall_timeout: Duration) -> Self {
+        self.retry = retry;
+        self.stall_timeout = stall_timeout;
+        self
+    }
+
+    /// Report download progress to `progress`
+    pub fn with_progress(mut self, progress: Progress) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Where download progress is reported
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    /// The HTTP client used for downloads
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Download a package from URL to destination path with progress.
+    ///
+    /// Data is written to `<destination>.part` and renamed into place once complete. If a
+    /// `.part` file is left over from an interrupted run, the download resumes from its end.
+    /// Transient failures (connection errors, retryable statuses, stalls) are retried per the
+    /// retry policy, each retry resuming from what is already on disk.
+    /// `file://` URLs (a mirror on a network share) are copied instead.
     pub async fn download(&self, url: &str, destination: &Path) -> CudaMgrResult<()> {
-        let mut response = self
-            .client
-            .get(url)
+        let part = part_path(destination);
+        if let Some(source) = redist::file_url_path(url) {
+            let name = progress_name(destination);
+            let size = std::fs::metadata(&source).map(|m| m.len()).ok();
+            self.progress.file_started(Stage::Download, &name, size, 0);
+            let copied = std::fs::copy(&source, &part)
+                .map_err(|e| InstallError::Download(format!("Copy {}: {}", source.display(), e)))?;
+            self.progress.file_progress(&name, copied);
+            return std::fs::rename(&part, destination).map_err(|e| {
+                InstallError::Download(format!("Rename {}: {}", part.display(), e)).into()
+            });
+        }
+        let what = format!("Download of {}", url);
+        retry::run(&self.retry, &what, |_| self.download_once(url, destination)).await
+    }
+
+    /// One download attempt, resuming from `<destination>.part` when possible.
+    async fn download_once(&self, url: &str, destination: &Path) -> Result<(), Failure> {
+        let part = part_path(destination);
+        let mut offset = std::fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+        if offset > 0 && !self.supports_ranges(url).await {
+            tracing::debug!("{} does not advertise Accept-Ranges; restarting", url);
+            offset = 0;
+        }
+
+        let mut request = self.client.get(url);
+        if offset > 0 {
+            tracing::info!("Resuming {} from byte {}", url, offset);
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = request
             .send()
             .await
-            .map_err(|e| InstallError::Download(format!("Request failed: {}", e)))?;
+            .map_err(|e| Failure::from_reqwest("Request failed", &e))?;
+
+        let status = response.status();
+        if offset > 0 && status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            // The partial file does not fit the remote object (e.g. it changed); start over.
+            let _ = std::fs::remove_file(&part);
+            return Box::pin(self.download_once(url, destination)).await;
+        }
+        if !status.is_success() {
+            return Err(Failure::from_status(&self.retry, &response));
+        }
 
-        if !response.status().is_success() {
-            return Err(
-                InstallError::Download(format!("HTTP {} from {}", response.status(), url)).into(),
-            );
+        // 206 means the server honoured the range; a plain 200 carries the whole body.
+        let resuming = offset > 0 && status == reqwest::StatusCode::PARTIAL_CONTENT;
+        if offset > 0 && !resuming {
+            tracing::debug!("Server ignored Range for {}; downloading in full", url);
         }
+        let start = if resuming { offset } else { 0 };
+        let total_size = response.content_length().map(|len| start + len);
+        let name = progress_name(destination);
+        self.progress
+            .file_started(Stage::Download, &name, total_size, start);
 
-        let total_size = response.content_length().unwrap_or(0);
-        let mut file = std::fs::File::create(destination)
-            .map_err(|e| InstallError::Download(format!("Create file: {}", e)))?;
+        let mut file = if resuming {
+            std::fs::OpenOptions::new().append(true).open(&part)
+        } else {
+            std::fs::File::create(&part)
+        }
+        .map_err(|e| Failure::fatal(format!("Create file: {}", e)))?;
 
-        let mut downloaded: u64 = 0;
+        let mut downloaded = start;
         let mut last_pct = 0u64;
 
-        while let Some(chunk) = response
-            .chunk()
-            .await
-            .map_err(|e| InstallError::Download(format!("Stream: {}", e)))?
-        {
+        loop {
+            let chunk = tokio::time::timeout(self.stall_timeout, response.chunk())
+                .await
+                .map_err(|_| {
+                    Failure::retryable(format!(
+                        "Stalled: no data for {}s after {} bytes",
+                        self.stall_timeout.as_secs(),
+                        downloaded
+                    ))
+                })?
+                .map_err(|e| Failure::from_reqwest("Stream", &e))?;
+            let Some(chunk) = chunk else {
+                break;
+            };
             file.write_all(&chunk)
-                .map_err(|e| InstallError::Download(format!("Write: {}", e)))?;
+                .map_err(|e| Failure::fatal(format!("Write: {}", e)))?;
             downloaded += chunk.len() as u64;
-            if total_size > 0 {
-                let pct = (downloaded * 100) / total_size;
-                if pct >= last_pct + 10 || downloaded == total_size {
+            self.progress.file_progress(&name, downloaded);
+            if let Some(pct) = total_size.and_then(|t| (downloaded * 100).checked_div(t)) {
+                if pct >= last_pct + 10 || Some(downloaded) == total_size {
                     last_pct = pct;
                     tracing::debug!("Download progress: {}%", pct);
                 }
             }
         }
+        file.flush()
+            .map_err(|e| Failure::fatal(format!("Write: {}", e)))?;
+        drop(file);
 
+        if let Some(expected) = total_size {
+            if downloaded != expected {
+                // Keep the .part file so the next attempt can resume.
+                return Err(Failure::retryable(format!(
+                    "Incomplete download: got {} of {} bytes",
+                    downloaded, expected
+                )));
+            }
+        }
+
+        std::fs::rename(&part, destination)
+            .map_err(|e| Failure::fatal(format!("Rename {}: {}", part.display(), e)))?;
         Ok(())
     }
 
-    /// Base URL for NVIDIA redist packages
-    pub fn redist_base_url() -> &'static str {
-        REDIST_BASE
+    /// Whether the server advertises byte-range support for `url` (HEAD `Accept-Ranges: bytes`).
+    async fn supports_ranges(&self, url: &str) -> bool {
+        match self.client.head(url).send().await {
+            Ok(resp) => resp
+                .headers()
+                .get(reqwest::header::ACCEPT_RANGES)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.eq_ignore_ascii_case("bytes")),
+            Err(_) => false,
+        }
     }
 
-    /// Resume a partial download
+    /// Download an artifact and check it against its manifest entry.
+    ///
+    /// An existing file that already verifies is reused. A leftover `.part` file is resumed;
+    /// if the resumed file fails verification it is discarded and downloaded once more in full.
+    /// The outcome is reported to the progress reporter.
+    pub async fn fetch_artifact(
+        &self,
+        url: &str,
+        destination: &Path,
+        artifact: Option<&RedistArtifact>,
+        verify: bool,
+    ) -> CudaMgrResult<()> {
+        let name = progress_name(destination);
+        let result = self
+            .fetch_or_reuse(url, destination, artifact.filter(|_| verify))
+            .await;
+        let outcome = match &result {
+            Ok(true) => {
+                // Counts as done for the batch total
+                let size = std::fs::metadata(destination).map(|m| m.len()).ok();
+                self.progress
+                    .file_started(Stage::Download, &name, size, size.unwrap_or(0));
+                FileOutcome::Reused
+            }
+            Ok(false) => FileOutcome::Downloaded,
+            Err(e) => FileOutcome::Failed(e.to_string()),
+        };
+        self.progress.file_finished(&name, &outcome);
+        result.map(|_| ())
+    }
+
+    /// [`fetch_artifact`](Self::fetch_artifact) without the reporting; true when an existing
+    /// file was reused.
+    async fn fetch_or_reuse(
+        &self,
+        url: &str,
+        destination: &Path,
+        artifact: Option<&RedistArtifact>,
+    ) -> CudaMgrResult<bool> {
+        if let Some(artifact) = artifact {
+            if destination.exists() && self.verify_artifact(destination, artifact).await.is_ok() {
+                tracing::info!("Reusing verified {}", destination.display());
+                return Ok(true);
+            }
+        }
+
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                InstallError::Download(format!("Create {}: {}", parent.display(), e))
+            })?;
+        }
+        let resumed = part_path(destination).exists();
+        self.download(url, destination).await?;
+        let Some(artifact) = artifact else {
+            return Ok(false);
+        };
+        let verified = match self.verify_artifact(destination, artifact).await {
+            Err(e) if resumed => {
+                tracing::warn!(
+                    "Resumed download failed verification ({}); retrying in full",
+                    e
+                );
+                let _ = std::fs::remove_file(part_path(destination));
+                self.download(url, destination).await?;
+                self.verify_artifact(destination, artifact).await
+            }
+            other => other,
+        };
+        verified.map(|()| false)
+    }
+
+    /// Fetch a batch of artifacts with at most `max_concurrent` downloads in flight.
+    ///
+    /// Every job runs to completion (one failure does not cancel the others) and results are
+    /// returned in the same order as `jobs`, so callers can extract deterministically.
+    pub async fn fetch_all(
+        &self,
+        jobs: &[DownloadJob],
+        max_concurrent: usize,
+        verify: bool,
+    ) -> Vec<CudaMgrResult<()>> {
+        let sizes: Option<Vec<u64>> = jobs
+            .iter()
+            .map(|j| j.artifact.as_ref().and_then(|a| a.size))
+            .collect();
+        self.progress
+            .batch_started(Stage::Download, jobs.len(), sizes.map(|s| s.iter().sum()));
+        let semaphore = Arc::new(Semaphore::new(max_concurrent.max(1)));
+        let mut tasks = JoinSet::new();
+        for (index, job) in jobs.iter().cloned().enumerate() {
+            let downloader = self.clone();
+            let semaphore = Arc::clone(&semaphore);
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let result = downloader
+                    .fetch_artifact(&job.url, &job.destination, job.artifact.as_ref(), verify)
+                    .await;
+                (index, result)
+            });
+        }
+
+        let mut results: Vec<Option<CudaMgrResult<()>>> = jobs.iter().map(|_| None).collect();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, result)) => results[index] = Some(result),
+                Err(e) => tracing::error!("Download task failed: {}", e),
+            }
+        }
+        self.progress.batch_finished();
+        results
+            .into_iter()
+            .map(|r| {
+                r.unwrap_or_else(|| {
+                    Err(InstallError::Download("Download task aborted".to_string()).into())
+                })
+            })
+            .collect()
+    }
+
+    /// Resume a partial download (continues from `<destination>.part` when possible)
     pub async fn resume_download(&self, url: &str, destination: &Path) -> CudaMgrResult<()> {
-        // Fall back to full download; proper Range support can be added later
         self.download(url, destination).await
     }
 
-    /// Verify download integrity
+    /// Verify download integrity against an expected SHA-256 (hex, case-insensitive)
     pub async fn verify_integrity(
         &self,
-        _file_path: &Path,
-        _expected_hash: &str,
+        file_path: &Path,
+        expected_hash: &str,
     ) -> CudaMgrResult<bool> {
-        // TODO: Implement SHA256 verification
-        Ok(true)
+        // Hashing a multi-GB archive would stall the runtime's worker thread
+        let path = file_path.to_path_buf();
+        let actual = tokio::task::spawn_blocking(move || sha256_file(&path))
+            .await
+            .map_err(|e| InstallError::Validation(format!("Hash task failed: {}", e)))?
+            .map_err(|e| {
+                InstallError::Validation(format!("Hash {}: {}", file_path.display(), e))
+            })?;
+        Ok(actual.eq_ignore_ascii_case(expected_hash.trim()))
+    }
+
+    /// Check a downloaded archive against the size and sha256 from its redist manifest entry.
+    /// A corrupt or truncated file is deleted so it is never extracted or reused.
+    pub async fn verify_artifact(
+        &self,
+        file_path: &Path,
+        artifact: &RedistArtifact,
+    ) -> CudaMgrResult<()> {
+        let result = self.check_artifact(file_path, artifact).await;
+        if result.is_err() {
+            let _ = std::fs::remove_file(file_path);
+        }
+        result
+    }
+
+    /// Like [`verify_artifact`](Self::verify_artifact), but never touches the file. Used for
+    /// archives the user supplied (`install --from`), which are not ours to delete.
+    pub async fn check_artifact(
+        &self,
+        file_path: &Path,
+        artifact: &RedistArtifact,
+    ) -> CudaMgrResult<()> {
+        if let Some(expected_size) = artifact.size {
+            let actual_size = std::fs::metadata(file_path)
+                .map_err(|e| {
+                    InstallError::Validation(format!("Stat {}: {}", file_path.display(), e))
+                })?
+                .len();
+            if actual_size != expected_size {
+                return Err(InstallError::Validation(format!(
+                    "{}: size mismatch (expected {} bytes, got {})",
+                    artifact.file_name(),
+                    expected_size,
+                    actual_size
+                ))
+                .into());
+            }
+        }
+
+        match &artifact.sha256 {
+            Some(expected) => {
+                if !self.verify_integrity(file_path, expected).await? {
+                    return Err(InstallError::Validation(format!(
+                        "{}: sha256 mismatch (expected {})",
+                        artifact.file_name(),
+                        expected
+                    ))
+                    .into());
+                }
+            }
+            None => {
+                tracing::warn!(
+                    "No sha256 in manifest for {}; skipping hash check",
+                    artifact.file_name()
+                );
+            }
+        }
+        Ok(())
     }
 }
 
+/// Path of the in-progress download for `destination` (`<file name>.part`).
+pub fn part_path(destination: &Path) -> PathBuf {
+    let mut name = destination
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_default();
+    name.push(".part");
+    destination.with_file_name(name)
+}
+
+/// Compute the lowercase hex SHA-256 of a file, streaming it in chunks.
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 1 << 16];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 impl Default for PackageDownloader {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact(sha256: Option<&str>, size: Option<u64>) -> RedistArtifact {
+        RedistArtifact {
+            component: "cuda_cudart".to_string(),
+            version: None,
+            relative_path: "cuda_cudart/linux-x86_64/cudart.tar.xz".to_string(),
+            sha256: sha256.map(String::from),
+            size,
+        }
+    }
+
+    #[test]
+    fn test_sha256_file() {
+        let path = std::env::temp_dir().join("cudamgr_sha256_test.bin");
+        std::fs::write(&path, b"abc").unwrap();
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_verify_artifact_accepts_matching_file() {
+        let path = std::env::temp_dir().join("cudamgr_verify_ok.bin");
+        std::fs::write(&path, b"abc").unwrap();
+        let a = artifact(
+            Some("BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD"),
+            Some(3),
+        );
+        let downloader = PackageDownloader::new();
+        assert!(downloader.verify_artifact(&path, &a).await.is_ok());
+        assert!(path.exists());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_verify_artifact_removes_corrupt_file() {
+        let downloader = PackageDownloader::new();
+
+        let path = std::env::temp_dir().join("cudamgr_verify_bad_hash.bin");
+        std::fs::write(&path, b"abc").unwrap();
+        let a = artifact(Some("00"), None);
+        let err = downloader.verify_artifact(&path, &a).await.unwrap_err();
+        assert!(matches!(
+            err,
+            crate::error::CudaMgrError::Install(InstallError::Validation(_))
+        ));
+        assert!(!path.exists());
+
+        let path = std::env::temp_dir().join("cudamgr_verify_truncated.bin");
+        std::fs::write(&path, b"ab").unwrap();
+        let a = artifact(None, Some(3));
+        assert!(downloader.verify_artifact(&path, &a).await.is_err());
+        assert!(!path.exists());
+    }
+
+    const BODY: &[u8] = b"0123456789abcdef";
+
+    async fn read_request(sock: &mut tokio::net::TcpStream) -> String {
+        use tokio::io::AsyncReadExt;
+
+        let mut req = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !req.windows(4).any(|w| w == b"\r\n\r\n") {
+            let n = sock.read(&mut buf).await.unwrap_or(0);
+            if n == 0 {
+                break;
+            }
+            req.extend_from_slice(&buf[..n]);
+        }
+        String::from_utf8_lossy(&req).to_lowercase()
+    }
+
+    /// Answer `req` with BODY, honouring `Range: bytes=N-` when `ranges` is set.
+    async fn respond_with_body(sock: &mut tokio::net::TcpStream, req: &str, ranges: bool) {
+        use tokio::io::AsyncWriteExt;
+
+        let range_start = req
+            .lines()
+            .find_map(|l| l.strip_prefix("range: bytes="))
+            .and_then(|r| r.trim_end_matches('-').parse::<usize>().ok())
+            .filter(|_| ranges);
+        let accept = if ranges { "bytes" } else { "none" };
+        let (status, body) = match range_start {
+            Some(start) => ("206 Partial Content", &BODY[start..]),
+            None => ("200 OK", BODY),
+        };
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nAccept-Ranges: {}\r\nConnection: close\r\n\r\n",
+            status,
+            body.len(),
+            accept
+        );
+        let _ = sock.write_all(head.as_bytes()).await;
+        if !req.starts_with("head") {
+            let _ = sock.write_all(body).await;
+        }
+    }
+
+    /// Minimal HTTP/1.1 server serving BODY; honours `Range: bytes=N-` when `ranges` is set.
+    async fn serve_body(ranges: bool) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut sock, _)) = listener.accept().await {
+                let req = read_request(&mut sock).await;
+                respond_with_body(&mut sock, &req, ranges).await;
+            }
+        });
+        format!("http://{}/archive.tar.xz", addr)
+    }
+
+    /// Like [`serve_body`] with range support, but the first GET gets `first` written raw;
+    /// with `hang` the connection is then held open without sending anything more.
+    async fn serve_flaky(first: &'static [u8], hang: bool) -> String {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut failed = false;
+            while let Ok((mut sock, _)) = listener.accept().await {
+                let req = read_request(&mut sock).await;
+                if failed || req.starts_with("head") {
+                    respond_with_body(&mut sock, &req, true).await;
+                    continue;
+                }
+                failed = true;
+                let _ = sock.write_all(first).await;
+                if hang {
+                    tokio::spawn(async move {
+                        tokio::time::sleep(Duration::from_secs(30)).await;
+                        drop(sock);
+                    });
+                }
+            }
+        });
+        format!("http://{}/archive.tar.xz", addr)
+    }
+
+    fn fast_retry() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 10,
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_part_path() {
+        let p = part_path(Path::new("/tmp/x/cuda_nvcc.tar.xz"));
+        assert_eq!(p, Path::new("/tmp/x/cuda_nvcc.tar.xz.part"));
+    }
+
+    #[tokio::test]
+    async fn test_download_copies_file_urls() {
+        let dir = std::env::temp_dir().join("cudamgr_file_url_download");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("mirror.tar.xz");
+        std::fs::write(&source, b"archive").unwrap();
+        let url = reqwest::Url::from_file_path(&source).unwrap();
+
+        let dest = dir.join("out.tar.xz");
+        PackageDownloader::new()
+            .download(url.as_str(), &dest)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"archive");
+        assert!(!part_path(&dest).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_download_resumes_part_file() {
+        let url = serve_body(true).await;
+        let dest = std::env::temp_dir().join("cudamgr_resume_test.bin");
+        let _ = std::fs::remove_file(&dest);
+        std::fs::write(part_path(&dest), &BODY[..6]).unwrap();
+
+        PackageDownloader::new()
+            .download(&url, &dest)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), BODY);
+        assert!(!part_path(&dest).exists());
+        let _ = std::fs::remove_file(&dest);
+    }
+
+    #[tokio::test]
+    async fn test_download_restarts_without_range_support() {
+        let url = serve_body(false).await;
+        let dest = std::env::temp_dir().join("cudamgr_no_range_test.bin");
+        let _ = std::fs::remove_file(&dest);
+        std::fs::write(part_path(&dest), b"garbage").unwrap();
+
+        PackageDownloader::new()
+            .download(&url, &dest)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), BODY);
+        let _ = std::fs::remove_file(&dest);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_preserves_order_and_reports_failures() {
+        let url = serve_body(true).await;
+        let dir = std::env::temp_dir().join("cudamgr_fetch_all_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let jobs: Vec<DownloadJob> = (0..4)
+            .map(|i| DownloadJob {
+                url: url.clone(),
+                destination: dir.join(format!("a{}.bin", i)),
+                artifact: Some(RedistArtifact {
+                    component: format!("c{}", i),
+                    version: None,
+                    relative_path: format!("c{}/a{}.bin", i, i),
+                    sha256: None,
+                    // Job 2 expects the wrong size and must fail on its own.
+                    size: Some(if i == 2 { 1 } else { BODY.len() as u64 }),
+                }),
+            })
+            .collect();
+
+        let results = PackageDownloader::new().fetch_all(&jobs, 2, true).await;
+        assert_eq!(results.len(), 4);
+        for (i, r) in results.iter().enumerate() {
+            assert_eq!(r.is_ok(), i != 2, "job {}", i);
+        }
+        assert!(dir.join("a3.bin").exists());
+        assert!(!dir.join("a2.bin").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_download_retries_retryable_status() {
+        let url = serve_flaky(
+            b"HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            false,
+        )
+        .await;
+        let dest = std::env::temp_dir().join("cudamgr_retry_503.bin");
+        let _ = std::fs::remove_file(&dest);
+
+        PackageDownloader::new()
+            .with_retry(fast_retry(), Duration::from_secs(5))
+            .download(&url, &dest)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), BODY);
+        let _ = std::fs::remove_file(&dest);
+    }
+
+    #[tokio::test]
+    async fn test_download_gives_up_on_fatal_status() {
+        let url = serve_flaky(
+            b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            false,
+        )
+        .await;
+        let dest = std::env::temp_dir().join("cudamgr_retry_404.bin");
+        let err = PackageDownloader::new()
+            .with_retry(fast_retry(), Duration::from_secs(5))
+            .download(&url, &dest)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("after 1 attempt: HTTP 404"));
+    }
+
+    #[tokio::test]
+    async fn test_stalled_download_resumes() {
+        // Headers promise the full body, six bytes arrive, then the connection goes quiet.
+        let url = serve_flaky(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 16\r\nAccept-Ranges: bytes\r\n\r\n012345",
+            true,
+        )
+        .await;
+        let dest = std::env::temp_dir().join("cudamgr_retry_stall.bin");
+        let _ = std::fs::remove_file(&dest);
+        let _ = std::fs::remove_file(part_path(&dest));
+
+        PackageDownloader::new()
+            .with_retry(fast_retry(), Duration::from_millis(200))
+            .download(&url, &dest)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), BODY);
+        let _ = std::fs::remove_file(&dest);
+    }
+}
@@ -0,0 +1,836 @@
+//! Archive extraction for redist components.
+//!
+//! Archives are unpacked into a scratch directory and merged into the target tree. Entry names
+//! and symlink targets are checked so that a tampered archive cannot write outside the target:
+//! every directory written into is resolved on disk, links the archive already created
+//! included, and must lie inside the extraction or install root.
+
+use crate::error::{CudaMgrError, CudaMgrResult, InstallError};
+use crate::install::downloader::progress_name;
+use crate::install::progress::{FileOutcome, Progress, Stage};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Suffix of the scratch directories this process extracts into (`<archive>.<pid>_extract`),
+/// so runs sharing a cache directory never touch each other's.
+pub fn scratch_suffix() -> String {
+    format!(".{}_extract", std::process::id())
+}
+
+/// Extract an archive (.zip or .tar.xz) to a temp dir under `scratch_dir`, then merge contents
+/// into target. NVIDIA redist archives have one top-level dir (e.g. *-archive) with bin/, lib/, include/.
+/// The temp dir is removed again whether or not the merge succeeds. Returns the files and
+/// symlinks placed, relative to `target`. How much of the archive has been read is reported
+/// to `progress`.
+pub fn extract_and_merge(
+    archive_path: &Path,
+    scratch_dir: &Path,
+    target: &Path,
+    progress: &Progress,
+) -> CudaMgrResult<Vec<PathBuf>> {
+    let name = progress_name(archive_path);
+    let stem = archive_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("extract");
+    // For .tar.xz the stem is e.g. "cuda_nvcc-...-archive" (one less extension)
+    let extract_dir = scratch_dir.join(format!("{}{}", stem, scratch_suffix()));
+    // Never merge leftovers from an earlier, aborted extraction.
+    let _ = std::fs::remove_dir_all(&extract_dir);
+    let result = extract_and_merge_inner(archive_path, &extract_dir, target, progress, &name);
+    let _ = std::fs::remove_dir_all(&extract_dir);
+    let outcome = match &result {
+        Ok(_) => FileOutcome::Extracted,
+        Err(e) => FileOutcome::Failed(e.to_string()),
+    };
+    progress.file_finished(&name, &outcome);
+    result
+}
+
+fn extract_and_merge_inner(
+    archive_path: &Path,
+    extract_dir: &Path,
+    target: &Path,
+    progress: &Progress,
+    name: &str,
+) -> CudaMgrResult<Vec<PathBuf>> {
+    let ext = archive_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+
+    let is_zip = archive_path.to_string_lossy().ends_with(".zip");
+    let is_tar_xz = ext == "xz" || archive_path.to_string_lossy().ends_with(".tar.xz");
+    if !is_zip && !is_tar_xz {
+        return Err(InstallError::Installation(format!(
+            "Unsupported archive format: {}",
+            archive_path.display()
+        ))
+        .into());
+    }
+    let file = std::fs::File::open(archive_path)
+        .map_err(|e| InstallError::Download(format!("Open {}: {}", archive_path.display(), e)))?;
+    let size = file.metadata().map(|m| m.len()).ok();
+    progress.file_started(Stage::Extract, name, size, 0);
+    let reader = ProgressReader {
+        inner: file,
+        position: 0,
+        progress,
+        name,
+    };
+    if is_zip {
+        extract_zip(reader, extract_dir)?;
+    } else {
+        extract_tar_xz(reader, extract_dir)?;
+    }
+
+    // Merge: find the single top-level dir (e.g. *-archive) and copy its contents into target.
+    // NVIDIA zips can be either: (1) one wrapper dir *-archive with bin/lib/include inside,
+    // or (2) just bin/ (and/or lib/, include/) at root. We must merge so files end up in target/bin etc.
+    let entries: Vec<_> = std::fs::read_dir(extract_dir)
+        .map_err(|e| {
+            CudaMgrError::Install(InstallError::Installation(format!(
+                "Read extract dir: {}",
+                e
+            )))
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| {
+            CudaMgrError::Install(InstallError::Installation(format!("Read dir entry: {}", e)))
+        })?;
+
+    let content_root = if entries.len() == 1 {
+        let e = &entries[0];
+        let p = e.path();
+        if p.is_dir() {
+            p
+        } else {
+            extract_dir.to_path_buf()
+        }
+    } else {
+        extract_dir.to_path_buf()
+    };
+
+    // If the only top-level entry is "bin", "lib", or "include", merge it into target/bin etc.,
+    // so we don't put nvcc.exe directly in target/.
+    let merge_dest = content_root
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(|s| match s {
+            "bin" => target.join("bin"),
+            "lib" => target.join("lib"),
+            "include" => target.join("include"),
+            _ => target.to_path_buf(),
+        })
+        .unwrap_or_else(|| target.to_path_buf());
+
+    std::fs::create_dir_all(target).map_err(|e| {
+        CudaMgrError::Install(InstallError::Installation(format!("Create dir: {}", e)))
+    })?;
+    let root = canonical_root(target)?;
+    let mut placed = Vec::new();
+    merge_dir_into(&content_root, &merge_dest, &root, &mut placed)?;
+    Ok(placed
+        .into_iter()
+        .filter_map(|p| p.strip_prefix(target).ok().map(Path::to_path_buf))
+        .collect())
+}
+
+/// Archive reader that reports how far into the file it is.
+struct ProgressReader<'a> {
+    inner: std::fs::File,
+    position: u64,
+    progress: &'a Progress,
+    name: &'a str,
+}
+
+impl io::Read for ProgressReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.position += n as u64;
+        self.progress.file_progress(self.name, self.position);
+        Ok(n)
+    }
+}
+
+impl io::Seek for ProgressReader<'_> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.position = self.inner.seek(pos)?;
+        Ok(self.position)
+    }
+}
+
+fn extract_zip(file: ProgressReader<'_>, dest: &Path) -> CudaMgrResult<()> {
+    std::fs::create_dir_all(dest).map_err(|e| {
+        CudaMgrError::Install(InstallError::Installation(format!("Create dir: {}", e)))
+    })?;
+    let root = canonical_root(dest)?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| {
+        CudaMgrError::Install(InstallError::Installation(format!("Invalid zip: {}", e)))
+    })?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| {
+            CudaMgrError::Install(InstallError::Installation(format!("Zip entry: {}", e)))
+        })?;
+        // Normalize path: zip may use backslashes on Windows; use forward slash for correct structure
+        let name = entry.name().replace('\\', "/");
+        let rel = safe_relative_path(&name)?;
+        let out_path = dest.join(&rel);
+        if entry.is_dir() {
+            create_dir_within(&root, &out_path)?;
+        } else {
+            if let Some(p) = out_path.parent() {
+                create_dir_within(&root, p)?;
+            }
+            if entry.is_symlink() {
+                let mut target = String::new();
+                io::Read::read_to_string(&mut entry, &mut target).map_err(|e| {
+                    CudaMgrError::Install(InstallError::Installation(format!("Zip link: {}", e)))
+                })?;
+                check_link_target(&root, &out_path, Path::new(&target))?;
+                create_symlink(Path::new(&target), &out_path)?;
+                continue;
+            }
+            // Never write through a link an earlier entry left at this name
+            remove_existing(&out_path)?;
+            let mut out = std::fs::File::create(&out_path).map_err(|e| {
+                CudaMgrError::Install(InstallError::Installation(format!("Create file: {}", e)))
+            })?;
+            io::copy(&mut entry, &mut out).map_err(|e| {
+                CudaMgrError::Install(InstallError::Installation(format!("Copy: {}", e)))
+            })?;
+            drop(out);
+            #[cfg(unix)]
+            if let Some(mode) = entry.unix_mode() {
+                use std::os::unix::fs::PermissionsExt;
+                let perms = std::fs::Permissions::from_mode(mode & 0o7777);
+                std::fs::set_permissions(&out_path, perms).map_err(|e| {
+                    CudaMgrError::Install(InstallError::Installation(format!("Chmod: {}", e)))
+                })?;
+            }
+            if let Some(modified) = entry.last_modified().and_then(zip_mtime) {
+                set_mtime(&out_path, modified);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn extract_tar_xz(file: ProgressReader<'_>, dest: &Path) -> CudaMgrResult<()> {
+    std::fs::create_dir_all(dest).map_err(|e| {
+        CudaMgrError::Install(InstallError::Installation(format!("Create dir: {}", e)))
+    })?;
+    let root = canonical_root(dest)?;
+    let dec = xz2::read::XzDecoder::new(file);
+    let mut archive = tar::Archive::new(dec);
+    let entries = archive.entries().map_err(|e| {
+        CudaMgrError::Install(InstallError::Installation(format!("Unpack tar: {}", e)))
+    })?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| {
+            CudaMgrError::Install(InstallError::Installation(format!("Tar entry: {}", e)))
+        })?;
+        let raw_path = entry.path_bytes().into_owned();
+        let rel = safe_relative_path(&String::from_utf8_lossy(&raw_path))?;
+        let out_path = dest.join(&rel);
+        if let Some(p) = out_path.parent() {
+            create_dir_within(&root, p)?;
+        }
+
+        let kind = entry.header().entry_type();
+        if kind.is_symlink() || kind.is_hard_link() {
+            let link = entry
+                .link_name()
+                .map_err(|e| {
+                    CudaMgrError::Install(InstallError::Installation(format!("Tar link: {}", e)))
+                })?
+                .ok_or_else(|| {
+                    InstallError::UnsafeArchive(format!("{}: link without target", rel.display()))
+                })?
+                .into_owned();
+            if kind.is_symlink() {
+                check_link_target(&root, &out_path, &link)?;
+            } else {
+                // Hard link targets are relative to the archive root.
+                let linked = safe_relative_path(&link.to_string_lossy())?;
+                ensure_within(&root, &dest.join(linked))?;
+            }
+        }
+
+        let unpacked = entry.unpack_in(dest).map_err(|e| {
+            CudaMgrError::Install(InstallError::Installation(format!("Unpack tar: {}", e)))
+        })?;
+        if !unpacked {
+            return Err(InstallError::UnsafeArchive(format!(
+                "{}: refused by tar unpacker",
+                rel.display()
+            ))
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Turn an archive entry name into a path relative to the extraction root, rejecting
+/// absolute paths, drive prefixes and `..` components.
+fn safe_relative_path(name: &str) -> CudaMgrResult<PathBuf> {
+    let normalized = name.replace('\\', "/");
+    let unsafe_entry =
+        || InstallError::UnsafeArchive(format!("{}: escapes the install directory", name));
+    if normalized.starts_with('/') {
+        return Err(unsafe_entry().into());
+    }
+    let mut rel = PathBuf::new();
+    for (i, segment) in normalized.split('/').enumerate() {
+        match segment {
+            "" | "." => continue,
+            ".." => return Err(unsafe_entry().into()),
+            // "C:" style drive prefixes are absolute on Windows.
+            s if i == 0 && s.ends_with(':') => return Err(unsafe_entry().into()),
+            s => rel.push(s),
+        }
+    }
+    if rel.as_os_str().is_empty() {
+        return Err(InstallError::UnsafeArchive(format!("{:?}: empty entry name", name)).into());
+    }
+    Ok(rel)
+}
+
+/// `dir` resolved on disk, as the root that extracted paths must stay inside.
+fn canonical_root(dir: &Path) -> CudaMgrResult<PathBuf> {
+    dir.canonicalize().map_err(|e| {
+        CudaMgrError::Install(InstallError::Installation(format!(
+            "Resolve {}: {}",
+            dir.display(),
+            e
+        )))
+    })
+}
+
+/// Fail unless `path`, with every symlink in its existing part resolved, lies inside `root`
+/// (canonical). Components that don't exist yet are fine: entry names carry no `..`, so they
+/// become plain directories or files below the deepest existing one.
+fn ensure_within(root: &Path, path: &Path) -> CudaMgrResult<()> {
+    let escapes = || {
+        InstallError::UnsafeArchive(format!(
+            "{}: resolves outside {}",
+            path.display(),
+            root.display()
+        ))
+    };
+    let mut existing = Some(path);
+    while let Some(p) = existing {
+        if std::fs::symlink_metadata(p).is_ok() {
+            // A dangling link can't be resolved, so it can't be shown to stay inside
+            let real = p.canonicalize().map_err(|_| escapes())?;
+            return if real.starts_with(root) {
+                Ok(())
+            } else {
+                Err(escapes().into())
+            };
+        }
+        existing = p.parent();
+    }
+    Err(escapes().into())
+}
+
+/// `create_dir_all`, after checking that `dir` can't resolve outside `root`.
+fn create_dir_within(root: &Path, dir: &Path) -> CudaMgrResult<()> {
+    ensure_within(root, dir)?;
+    std::fs::create_dir_all(dir)
+        .map_err(|e| CudaMgrError::Install(InstallError::Installation(format!("Mkdir: {}", e))))
+}
+
+/// Reject a symlink at `link` whose target would resolve outside `root` (canonical). The
+/// link's directory must already exist; `..` is measured from where it really is on disk,
+/// so links created earlier in `link`'s path are accounted for. A target may only climb
+/// before it descends: once it has passed through a name that could itself be a link, where
+/// a later `..` lands can't be known until every entry is on disk.
+fn check_link_target(root: &Path, link: &Path, target: &Path) -> CudaMgrResult<()> {
+    let escapes = || {
+        InstallError::UnsafeArchive(format!(
+            "{} -> {}: symlink points outside the install directory",
+            link.display(),
+            target.display()
+        ))
+    };
+    let target_str = target.to_string_lossy().replace('\\', "/");
+    if target_str.starts_with('/') {
+        return Err(escapes().into());
+    }
+    let mut climbs = 0;
+    let mut descended = false;
+    for segment in target_str.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." if descended => return Err(escapes().into()),
+            ".." => climbs += 1,
+            s if s.ends_with(':') => return Err(escapes().into()),
+            _ => descended = true,
+        }
+    }
+    let dir = link
+        .parent()
+        .and_then(|p| p.canonicalize().ok())
+        .ok_or_else(escapes)?;
+    // How many directories below the root the link really lives; `..` may climb that far
+    let depth = dir
+        .strip_prefix(root)
+        .map_err(|_| escapes())?
+        .components()
+        .count();
+    if climbs > depth {
+        return Err(escapes().into());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> CudaMgrResult<()> {
+    let _ = std::fs::remove_file(link);
+    std::os::unix::fs::symlink(target, link).map_err(|e| {
+        CudaMgrError::Install(InstallError::Installation(format!(
+            "Symlink {}: {}",
+            link.display(),
+            e
+        )))
+    })
+}
+
+#[cfg(not(unix))]
+fn create_symlink(target: &Path, link: &Path) -> CudaMgrResult<()> {
+    // Redist zips are Windows-only and carry no links; fall back to a copy of the target.
+    let source = link.parent().unwrap_or(Path::new(".")).join(target);
+    std::fs::copy(&source, link).map(|_| ()).map_err(|e| {
+        CudaMgrError::Install(InstallError::Installation(format!(
+            "Copy link target {}: {}",
+            source.display(),
+            e
+        )))
+    })
+}
+
+/// Move everything under `src` into `target`, merging directories, with the same checks as an
+/// extraction. Returns the files and symlinks placed, relative to `target`.
+pub fn merge_tree(src: &Path, target: &Path) -> CudaMgrResult<Vec<PathBuf>> {
+    std::fs::create_dir_all(target).map_err(|e| {
+        CudaMgrError::Install(InstallError::Installation(format!("Create dir: {}", e)))
+    })?;
+    let root = canonical_root(target)?;
+    let mut placed = Vec::new();
+    merge_dir_into(src, target, &root, &mut placed)?;
+    Ok(placed
+        .into_iter()
+        .filter_map(|p| p.strip_prefix(target).ok().map(Path::to_path_buf))
+        .collect())
+}
+
+/// Recursively move contents of src into target (merge: existing dirs are merged).
+/// Symlinks are recreated as symlinks rather than followed, and files are renamed into place
+/// when `src` and `target` share a filesystem, falling back to a copy that keeps mode and mtime.
+/// Links already in the tree are resolved against `root` (canonical) before anything is
+/// written through them, and copied links are checked again where they land.
+fn merge_dir_into(
+    src: &Path,
+    target: &Path,
+    root: &Path,
+    placed: &mut Vec<PathBuf>,
+) -> CudaMgrResult<()> {
+    for entry in std::fs::read_dir(src).map_err(|e| {
+        CudaMgrError::Install(InstallError::Installation(format!("Read dir: {}", e)))
+    })? {
+        let entry = entry.map_err(|e| {
+            CudaMgrError::Install(InstallError::Installation(format!("Dir entry: {}", e)))
+        })?;
+        let path = entry.path();
+        let dest = target.join(entry.file_name());
+        let file_type = entry.file_type().map_err(|e| {
+            CudaMgrError::Install(InstallError::Installation(format!("Dir entry: {}", e)))
+        })?;
+        if let Some(p) = dest.parent() {
+            create_dir_within(root, p)?;
+        }
+        if file_type.is_symlink() {
+            let link_target = std::fs::read_link(&path).map_err(|e| {
+                CudaMgrError::Install(InstallError::Installation(format!("Read link: {}", e)))
+            })?;
+            check_link_target(root, &dest, &link_target)?;
+            remove_existing(&dest)?;
+            create_symlink(&link_target, &dest)?;
+            placed.push(dest);
+        } else if file_type.is_dir() {
+            create_dir_within(root, &dest)?;
+            merge_dir_into(&path, &dest, root, placed)?;
+        } else {
+            move_file(&path, &dest)?;
+            placed.push(dest);
+        }
+    }
+    Ok(())
+}
+
+/// Rename `src` to `dest`, or copy it (with permissions and mtime) across filesystems.
+fn move_file(src: &Path, dest: &Path) -> CudaMgrResult<()> {
+    remove_existing(dest)?;
+    if std::fs::rename(src, dest).is_ok() {
+        return Ok(());
+    }
+    // fs::copy carries the permission bits over; the mtime has to be set by hand.
+    std::fs::copy(src, dest)
+        .map_err(|e| CudaMgrError::Install(InstallError::Installation(format!("Copy: {}", e))))?;
+    if let Ok(modified) = std::fs::metadata(src).and_then(|m| m.modified()) {
+        set_mtime(dest, modified);
+    }
+    Ok(())
+}
+
+/// Remove a file or symlink at `path` so it can be replaced; directories are left alone.
+fn remove_existing(path: &Path) -> CudaMgrResult<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(meta) if !meta.is_dir() => std::fs::remove_file(path).map_err(|e| {
+            CudaMgrError::Install(InstallError::Installation(format!(
+                "Replace {}: {}",
+                path.display(),
+                e
+            )))
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn set_mtime(path: &Path, modified: std::time::SystemTime) {
+    let mtime = filetime::FileTime::from_system_time(modified);
+    if let Err(e) = filetime::set_file_mtime(path, mtime) {
+        tracing::debug!("Could not set mtime on {}: {}", path.display(), e);
+    }
+}
+
+/// Convert a zip (MS-DOS) timestamp to a `SystemTime`, treating it as UTC.
+fn zip_mtime(dt: zip::DateTime) -> Option<std::time::SystemTime> {
+    let naive =
+        chrono::NaiveDate::from_ymd_opt(dt.year().into(), dt.month().into(), dt.day().into())?
+            .and_hms_opt(dt.hour().into(), dt.minute().into(), dt.second().into())?;
+    Some(naive.and_utc().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn fresh_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let file = std::fs::File::create(path).unwrap();
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, data) in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    /// Build a .tar.xz, writing entry names straight into the header so `..` survives.
+    fn write_tar_xz(path: &Path, entries: &[(&str, tar::EntryType, &str, &[u8])]) {
+        let file = std::fs::File::create(path).unwrap();
+        let mut builder = tar::Builder::new(xz2::write::XzEncoder::new(file, 1));
+        for (name, kind, link, data) in entries {
+            let mut header = tar::Header::new_gnu();
+            let gnu = header.as_gnu_mut().unwrap();
+            gnu.name[..name.len()].copy_from_slice(name.as_bytes());
+            gnu.linkname[..link.len()].copy_from_slice(link.as_bytes());
+            header.set_entry_type(*kind);
+            header.set_mode(0o644);
+            header.set_size(data.len() as u64);
+            header.set_cksum();
+            builder.append(&header, *data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn test_safe_relative_path() {
+        assert_eq!(
+            safe_relative_path("pkg-archive/bin/nvcc").unwrap(),
+            Path::new("pkg-archive/bin/nvcc")
+        );
+        assert_eq!(
+            safe_relative_path("./pkg\\bin\\nvcc.exe").unwrap(),
+            Path::new("pkg/bin/nvcc.exe")
+        );
+        for bad in [
+            "../evil",
+            "a/../../evil",
+            "/etc/passwd",
+            "\\evil",
+            "C:/evil",
+            "C:",
+        ] {
+            assert!(safe_relative_path(bad).is_err(), "{} accepted", bad);
+        }
+    }
+
+    #[test]
+    fn test_check_link_target() {
+        let dir = fresh_dir("cudamgr_extract_link_check");
+        std::fs::create_dir_all(dir.join("pkg/lib")).unwrap();
+        let root = dir.canonicalize().unwrap();
+        let link = root.join("pkg/lib/libcudart.so");
+        let check = |target: &str| check_link_target(&root, &link, Path::new(target));
+        assert!(check("libcudart.so.12").is_ok());
+        assert!(check("../../pkg/include").is_ok());
+        assert!(check("../../../etc").is_err());
+        assert!(check("/etc/passwd").is_err());
+        // `..` after a name that may be a link can't be resolved ahead of time
+        assert!(check("../x/../..").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// `a/b -> ..` is fine on its own, but `a/b/c -> ..` really sits in the root, so it
+    /// points at the root's parent and `a/b/c/evil` would land outside.
+    #[cfg(unix)]
+    #[test]
+    fn test_chained_links_are_rejected() {
+        let root = fresh_dir("cudamgr_extract_chain");
+        let target = root.join("install");
+
+        let zip = root.join("chain-archive.zip");
+        let file = std::fs::File::create(&zip).unwrap();
+        let mut writer = zip::ZipWriter::new(file);
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_symlink("a/b", "..", options).unwrap();
+        writer.add_symlink("a/b/c", "..", options).unwrap();
+        writer.start_file("a/b/c/evil", options).unwrap();
+        writer.write_all(b"pwned").unwrap();
+        writer.finish().unwrap();
+        let err = extract_and_merge(&zip, &root, &target, &Progress::default()).unwrap_err();
+        assert!(matches!(
+            err,
+            CudaMgrError::Install(InstallError::UnsafeArchive(_))
+        ));
+        assert!(!root.join("evil").exists());
+
+        let tar = root.join("chain-archive.tar.xz");
+        write_tar_xz(
+            &tar,
+            &[
+                ("a/b", tar::EntryType::Symlink, "..", b""),
+                ("a/b/c", tar::EntryType::Symlink, "..", b""),
+                ("a/b/c/evil", tar::EntryType::Regular, "", b"pwned"),
+            ],
+        );
+        let err = extract_and_merge(&tar, &root, &target, &Progress::default()).unwrap_err();
+        assert!(matches!(
+            err,
+            CudaMgrError::Install(InstallError::UnsafeArchive(_))
+        ));
+        assert!(!root.join("evil").exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    /// A directory link already in the install tree must not carry a merge outside it.
+    #[cfg(unix)]
+    #[test]
+    fn test_merge_does_not_follow_links_out_of_target() {
+        let root = fresh_dir("cudamgr_extract_merge_link");
+        let target = root.join("install");
+        let outside = root.join("outside");
+        std::fs::create_dir_all(&target).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, target.join("lib")).unwrap();
+
+        let zip = root.join("lib-archive.zip");
+        write_zip(&zip, &[("lib-archive/lib/libfoo.so", b"elf")]);
+        let err = extract_and_merge(&zip, &root, &target, &Progress::default()).unwrap_err();
+        assert!(matches!(
+            err,
+            CudaMgrError::Install(InstallError::UnsafeArchive(_))
+        ));
+        assert!(!outside.join("libfoo.so").exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_zip_traversal_is_rejected() {
+        let root = fresh_dir("cudamgr_extract_zip_evil");
+        let target = root.join("install");
+        for name in ["../evil.txt", "/tmp/cudamgr_evil.txt"] {
+            let archive = root.join("evil-archive.zip");
+            write_zip(&archive, &[("pkg/bin/ok", b"ok"), (name, b"pwned")]);
+            let err =
+                extract_and_merge(&archive, &root, &target, &Progress::default()).unwrap_err();
+            assert!(matches!(
+                err,
+                CudaMgrError::Install(InstallError::UnsafeArchive(_))
+            ));
+        }
+        assert!(!root.join("evil.txt").exists());
+        assert!(!Path::new("/tmp/cudamgr_evil.txt").exists());
+        // The scratch directory goes even when extraction fails
+        assert!(!root
+            .join(format!("evil-archive{}", scratch_suffix()))
+            .exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_tar_traversal_is_rejected() {
+        let root = fresh_dir("cudamgr_extract_tar_evil");
+        let target = root.join("install");
+        let archive = root.join("evil-archive.tar.xz");
+        write_tar_xz(
+            &archive,
+            &[("../evil.txt", tar::EntryType::Regular, "", b"pwned")],
+        );
+        let err = extract_and_merge(&archive, &root, &target, &Progress::default()).unwrap_err();
+        assert!(matches!(
+            err,
+            CudaMgrError::Install(InstallError::UnsafeArchive(_))
+        ));
+        assert!(!root.join("evil.txt").exists());
+
+        write_tar_xz(
+            &archive,
+            &[(
+                "pkg/lib/escape",
+                tar::EntryType::Symlink,
+                "../../../etc",
+                b"",
+            )],
+        );
+        assert!(extract_and_merge(&archive, &root, &target, &Progress::default()).is_err());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_benign_archives_extract() {
+        let root = fresh_dir("cudamgr_extract_ok");
+        let target = root.join("install");
+
+        let zip = root.join("a-archive.zip");
+        write_zip(&zip, &[("a-archive/bin/nvcc.exe", b"exe")]);
+        let placed = extract_and_merge(&zip, &root, &target, &Progress::default()).unwrap();
+        assert_eq!(placed, vec![PathBuf::from("bin/nvcc.exe")]);
+        assert!(target.join("bin/nvcc.exe").exists());
+
+        let tar = root.join("b-archive.tar.xz");
+        write_tar_xz(
+            &tar,
+            &[(
+                "b-archive/include/cuda.h",
+                tar::EntryType::Regular,
+                "",
+                b"h",
+            )],
+        );
+        extract_and_merge(&tar, &root, &target, &Progress::default()).unwrap();
+        assert_eq!(std::fs::read(target.join("include/cuda.h")).unwrap(), b"h");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_merge_keeps_symlinks_and_modes() {
+        use std::os::unix::fs::PermissionsExt;
+        let root = fresh_dir("cudamgr_extract_links");
+        let target = root.join("install");
+
+        let tar = root.join("cudart-archive.tar.xz");
+        write_tar_xz(
+            &tar,
+            &[
+                (
+                    "cudart-archive/lib/libcudart.so.12.4.127",
+                    tar::EntryType::Regular,
+                    "",
+                    b"elf",
+                ),
+                (
+                    "cudart-archive/lib/libcudart.so.12",
+                    tar::EntryType::Symlink,
+                    "libcudart.so.12.4.127",
+                    b"",
+                ),
+                (
+                    "cudart-archive/lib/libcudart.so",
+                    tar::EntryType::Symlink,
+                    "libcudart.so.12",
+                    b"",
+                ),
+            ],
+        );
+        extract_and_merge(&tar, &root, &target, &Progress::default()).unwrap();
+        let lib = target.join("lib");
+        assert_eq!(
+            std::fs::read_link(lib.join("libcudart.so")).unwrap(),
+            Path::new("libcudart.so.12")
+        );
+        assert_eq!(
+            std::fs::read_link(lib.join("libcudart.so.12")).unwrap(),
+            Path::new("libcudart.so.12.4.127")
+        );
+        assert_eq!(std::fs::read(lib.join("libcudart.so")).unwrap(), b"elf");
+
+        let zip = root.join("nvcc-archive.zip");
+        let file = std::fs::File::create(&zip).unwrap();
+        let mut writer = zip::ZipWriter::new(file);
+        let modified = zip::DateTime::from_date_and_time(2024, 3, 1, 12, 0, 0).unwrap();
+        let options = zip::write::SimpleFileOptions::default()
+            .unix_permissions(0o755)
+            .last_modified_time(modified);
+        writer.start_file("nvcc-archive/bin/nvcc", options).unwrap();
+        writer.write_all(b"#!/bin/sh").unwrap();
+        writer.finish().unwrap();
+        extract_and_merge(&zip, &root, &target, &Progress::default()).unwrap();
+        let meta = std::fs::metadata(target.join("bin/nvcc")).unwrap();
+        assert_eq!(meta.permissions().mode() & 0o777, 0o755);
+        assert_eq!(meta.modified().unwrap(), zip_mtime(modified).unwrap());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[derive(Default)]
+    struct Recorder(std::sync::Mutex<Vec<String>>);
+
+    impl crate::install::progress::ProgressReporter for Recorder {
+        fn file_started(&self, stage: Stage, file: &str, total: Option<u64>, _offset: u64) {
+            let event = format!("{:?} {} {:?}", stage, file, total);
+            self.0.lock().unwrap().push(event);
+        }
+
+        fn file_progress(&self, _file: &str, done: u64) {
+            self.0.lock().unwrap().push(done.to_string());
+        }
+
+        fn file_finished(&self, file: &str, outcome: &FileOutcome) {
+            let event = format!("{} {:?}", file, outcome);
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[test]
+    fn test_extract_reports_progress() {
+        let root = fresh_dir("cudamgr_extract_progress");
+        let target = root.join("target");
+        let zip = root.join("cudart-archive.zip");
+        write_zip(&zip, &[("cudart-archive/include/cuda.h", b"header")]);
+        let size = std::fs::metadata(&zip).unwrap().len();
+
+        let recorder = std::sync::Arc::new(Recorder::default());
+        let progress = Progress::from_arc(recorder.clone());
+        extract_and_merge(&zip, &root, &target, &progress).unwrap();
+        let events = recorder.0.lock().unwrap().clone();
+        assert_eq!(
+            events.first().unwrap(),
+            &format!("Extract cudart-archive.zip Some({})", size)
+        );
+        assert_eq!(events.last().unwrap(), "cudart-archive.zip Extracted");
+        assert!(events.iter().any(|e| e.parse::<u64>().is_ok_and(|n| n > 0)));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,119 @@
+//! HTTP client construction.
+//!
+//! Every request cudamgr makes (manifests, archives, the compatibility registry) goes through a
+//! client built here, so proxy settings, timeouts and the user agent are applied consistently.
+
+use crate::config::{CudaMgrConfig, NetworkConfig, ProxyConfig};
+use crate::error::{ConfigError, CudaMgrResult};
+use std::time::Duration;
+
+/// User agent sent with every request.
+pub const USER_AGENT: &str = concat!("cudamgr/", env!("CARGO_PKG_VERSION"));
+
+/// Build a client from the user's config, or from defaults when there is none.
+pub fn client(config: Option<&CudaMgrConfig>) -> CudaMgrResult<reqwest::Client> {
+    match config {
+        Some(config) => build_client(config.proxy_settings.as_ref(), &config.network),
+        None => build_client(None, &NetworkConfig::default()),
+    }
+}
+
+/// Build a client for `config`: configured proxies and `no_proxy` rules, timeouts and user
+/// agent. Without `proxy_settings`, reqwest's usual `HTTP(S)_PROXY` environment handling applies.
+pub fn build_client(
+    proxy: Option<&ProxyConfig>,
+    network: &NetworkConfig,
+) -> CudaMgrResult<reqwest::Client> {
+    let mut builder = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(Duration::from_secs(network.connect_timeout_secs))
+        .read_timeout(Duration::from_secs(network.read_timeout_secs));
+
+    if let Some(proxy) = proxy {
+        // Explicit settings replace anything picked up from the environment.
+        builder = builder.no_proxy();
+        let no_proxy = reqwest::NoProxy::from_string(&proxy.no_proxy.join(","));
+        if let Some(url) = &proxy.http_proxy {
+            let p = reqwest::Proxy::http(url).map_err(|e| {
+                ConfigError::Network(format!("Invalid http_proxy '{}': {}", url, e))
+            })?;
+            builder = builder.proxy(p.no_proxy(no_proxy.clone()));
+        }
+        if let Some(url) = &proxy.https_proxy {
+            let p = reqwest::Proxy::https(url).map_err(|e| {
+                ConfigError::Network(format!("Invalid https_proxy '{}': {}", url, e))
+            })?;
+            builder = builder.proxy(p.no_proxy(no_proxy));
+        }
+    }
+
+    builder
+        .build()
+        .map_err(|e| ConfigError::Network(format!("Build HTTP client: {}", e)).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_client_with_proxy_settings() {
+        let proxy = ProxyConfig {
+            http_proxy: Some("http://proxy.example.com:8080".to_string()),
+            https_proxy: Some("http://proxy.example.com:8443".to_string()),
+            no_proxy: vec!["localhost".to_string(), ".internal".to_string()],
+        };
+        assert!(build_client(Some(&proxy), &NetworkConfig::default()).is_ok());
+        assert!(client(None).is_ok());
+    }
+
+    #[test]
+    fn test_build_client_rejects_bad_proxy() {
+        let proxy = ProxyConfig {
+            http_proxy: Some("not a url".to_string()),
+            https_proxy: None,
+            no_proxy: vec![],
+        };
+        let err = build_client(Some(&proxy), &NetworkConfig::default()).unwrap_err();
+        assert!(err.to_string().contains("http_proxy"));
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_through_configured_proxy() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // A fake proxy that records the request line it receives.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+
+        let proxy = ProxyConfig {
+            http_proxy: Some(format!("http://{}", addr)),
+            https_proxy: None,
+            no_proxy: vec![],
+        };
+        let client = build_client(Some(&proxy), &NetworkConfig::default()).unwrap();
+        let body = client
+            .get("http://redist.example.invalid/redistrib_12.4.1.json")
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body, "ok");
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("GET http://redist.example.invalid/redistrib_12.4.1.json"));
+        assert!(request.contains(USER_AGENT));
+    }
+}
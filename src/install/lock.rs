@@ -0,0 +1,205 @@
+//! Project lockfile (`cudamgr.lock`).
+//!
+//! A lock pins what a version spec resolved to: the full release, the manifest platform and
+//! every component archive with its version, relative path and sha256, as the redist
+//! manifest listed them. `cudamgr lock` writes one from an install plan; `install --locked`
+//! installs exactly those archives and fails when the manifest or a downloaded archive no
+//! longer matches.
+
+use crate::error::{CudaMgrResult, InstallError};
+use crate::install::redist::{self, RedistArtifact, RedistPlatform};
+use crate::install::InstallationPlan;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Default lock file name, looked up in the current directory.
+pub const LOCK_FILE: &str = "cudamgr.lock";
+
+/// Format written by this version; bumped on incompatible changes.
+pub const LOCK_FORMAT: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LockFile {
+    pub format: u32,
+    /// Version as requested when the lock was made (e.g. "12.4")
+    pub spec: String,
+    /// Full release it resolved to (e.g. "12.4.1")
+    pub cuda_version: String,
+    /// Manifest `release_label`
+    #[serde(default)]
+    pub release_label: Option<String>,
+    pub platform: RedistPlatform,
+    /// Locked archives in install order, each with its manifest sha256
+    pub components: Vec<RedistArtifact>,
+}
+
+impl LockFile {
+    /// Lock what `plan` resolved `spec` to. Every archive needs a manifest sha256.
+    pub fn from_plan(spec: &str, plan: &InstallationPlan) -> CudaMgrResult<Self> {
+        let platform = plan.platform.ok_or_else(|| {
+            InstallError::Validation(format!(
+                "The CUDA {} plan names no platform; it can't be locked",
+                plan.cuda_version
+            ))
+        })?;
+        if let Some(artifact) = plan.artifacts.iter().find(|a| a.sha256.is_none()) {
+            return Err(InstallError::Validation(format!(
+                "{} has no sha256 in the CUDA {} manifest; it can't be locked",
+                artifact.component, plan.cuda_version
+            ))
+            .into());
+        }
+        Ok(Self {
+            format: LOCK_FORMAT,
+            spec: spec.to_string(),
+            cuda_version: plan.cuda_version.clone(),
+            release_label: plan.release_label.clone(),
+            platform,
+            components: plan.artifacts.clone(),
+        })
+    }
+
+    /// Whether `version` asks for what the lock pins: its spec, or a prefix of its release.
+    pub fn matches(&self, version: &str) -> bool {
+        self.spec == version || redist::version_matches(&self.cuda_version, version)
+    }
+
+    /// Check the locked archives against the release manifest as published now and return
+    /// them. A component that is gone, or whose version, path or sha256 changed, is drift.
+    pub fn check_manifest(
+        &self,
+        manifest: &serde_json::Value,
+    ) -> CudaMgrResult<Vec<RedistArtifact>> {
+        let current = redist::platform_artifacts(manifest, self.platform);
+        let mut drift = Vec::new();
+        for locked in &self.components {
+            let Some(found) = current.iter().find(|a| a.component == locked.component) else {
+                drift.push(format!("{} is no longer listed", locked.component));
+                continue;
+            };
+            let same_sha = match (&found.sha256, &locked.sha256) {
+                (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+                _ => false,
+            };
+            if !same_sha {
+                drift.push(format!(
+                    "{} sha256 was {} and is now {}",
+                    locked.component,
+                    locked.sha256.as_deref().unwrap_or("-"),
+                    found.sha256.as_deref().unwrap_or("-")
+                ));
+            } else if found.relative_path != locked.relative_path || found.version != locked.version
+            {
+                drift.push(format!(
+                    "{} was {} and is now {}",
+                    locked.component, locked.relative_path, found.relative_path
+                ));
+            }
+        }
+        if drift.is_empty() {
+            return Ok(self.components.clone());
+        }
+        Err(InstallError::Validation(format!(
+            "CUDA {} ({}) no longer matches {}: {}",
+            self.cuda_version,
+            self.platform,
+            LOCK_FILE,
+            drift.join("; ")
+        ))
+        .into())
+    }
+
+    pub fn write(&self, path: &Path) -> CudaMgrResult<()> {
+        let body = serde_json::to_string_pretty(self)
+            .map_err(|e| InstallError::Installation(format!("Serialize lock: {}", e)))?;
+        std::fs::write(path, body + "\n")
+            .map_err(|e| InstallError::Installation(format!("Write {}: {}", path.display(), e)))?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> CudaMgrResult<Self> {
+        let body = std::fs::read_to_string(path).map_err(|e| {
+            let hint = if e.kind() == std::io::ErrorKind::NotFound {
+                " (create one with 'cudamgr lock <version>')"
+            } else {
+                ""
+            };
+            InstallError::Validation(format!("Read {}: {}{}", path.display(), e, hint))
+        })?;
+        let lock: Self = serde_json::from_str(&body)
+            .map_err(|e| InstallError::Validation(format!("Parse {}: {}", path.display(), e)))?;
+        if lock.format > LOCK_FORMAT {
+            return Err(InstallError::Validation(format!(
+                "{} uses lock format {}; this cudamgr reads up to {}",
+                path.display(),
+                lock.format,
+                LOCK_FORMAT
+            ))
+            .into());
+        }
+        Ok(lock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::install::test_plan;
+
+    fn manifest(cudart_sha: &str) -> serde_json::Value {
+        serde_json::json!({
+            "release_label": "12.4.1",
+            "cuda_cudart": {
+                "version": "12.4.127",
+                "linux-x86_64": {
+                    "relative_path": "cuda_cudart/linux-x86_64/cuda_cudart-linux-x86_64-12.4.127-archive.tar.xz",
+                    "sha256": cudart_sha,
+                    "size": "1000",
+                },
+            },
+            "cuda_nvcc": {
+                "version": "12.4.131",
+                "linux-x86_64": {
+                    "relative_path": "cuda_nvcc/linux-x86_64/cuda_nvcc-linux-x86_64-12.4.131-archive.tar.xz",
+                    "sha256": "b".repeat(64),
+                    "size": "2000",
+                },
+            },
+        })
+    }
+
+    fn plan(manifest: &serde_json::Value) -> InstallationPlan {
+        InstallationPlan {
+            release_label: redist::release_label(manifest),
+            ..test_plan(redist::platform_artifacts(
+                manifest,
+                RedistPlatform::LinuxX86_64,
+            ))
+        }
+    }
+
+    #[test]
+    fn test_lock_round_trip_and_drift() {
+        let published = manifest(&"a".repeat(64));
+        let lock = LockFile::from_plan("12.4", &plan(&published)).unwrap();
+        assert!(lock.matches("12.4") && lock.matches("12.4.1") && !lock.matches("12.6"));
+        assert_eq!(lock.check_manifest(&published).unwrap().len(), 2);
+
+        let path = std::env::temp_dir().join("cudamgr_test.lock");
+        lock.write(&path).unwrap();
+        assert_eq!(LockFile::load(&path).unwrap(), lock);
+        let _ = std::fs::remove_file(&path);
+
+        // Same release, re-published archive
+        let err = lock
+            .check_manifest(&manifest(&"c".repeat(64)))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("cuda_cudart sha256"), "{}", err);
+        assert!(!err.contains("cuda_nvcc"), "{}", err);
+
+        let mut unhashed = plan(&published);
+        unhashed.artifacts[0].sha256 = None;
+        assert!(LockFile::from_plan("12.4", &unhashed).is_err());
+    }
+}
@@ -1,13 +1,28 @@
+pub mod cache;
 pub mod cleanup;
+pub mod companion;
+pub mod dedupe;
 pub mod downloader;
+pub mod extract;
+pub mod http;
 pub mod installer;
+pub mod lock;
+pub mod preview;
+pub mod progress;
+pub mod receipt;
 pub mod redist;
+pub mod repair;
+pub mod retry;
+pub mod space;
+pub mod usage;
 pub mod validator;
 
 use crate::config::CudaMgrConfig;
 use crate::error::{CudaMgrError, CudaMgrResult, InstallError};
+use crate::system::compatibility::REGISTRY;
+use crate::system::storage::format_bytes;
+use extract::extract_and_merge;
 use serde::{Deserialize, Serialize};
-use std::io;
 use std::path::{Path, PathBuf};
 
 /// Installation plan containing all necessary information
@@ -21,6 +36,49 @@ pub struct InstallationPlan {
     pub install_path: PathBuf,
     pub required_driver: Option<String>,
     pub dependencies: Vec<Dependency>,
+    /// Manifest entries (sha256/size) for the artifacts in `download_urls`
+    #[serde(default)]
+    pub artifacts: Vec<redist::RedistArtifact>,
+    /// Redist components selected for this install
+    #[serde(default)]
+    pub components: Vec<String>,
+    /// Directory holding already-downloaded archives; when set, nothing is fetched
+    #[serde(default)]
+    pub source_dir: Option<PathBuf>,
+    /// Manifest platform the artifacts were chosen for
+    #[serde(default)]
+    pub platform: Option<redist::RedistPlatform>,
+    /// Manifest `release_label`
+    #[serde(default)]
+    pub release_label: Option<String>,
+}
+
+impl InstallationPlan {
+    /// Manifest entry for a download URL, if the plan carries one.
+    pub fn artifact_for_url(&self, url: &str) -> Option<&redist::RedistArtifact> {
+        self.artifacts
+            .iter()
+            .find(|a| url.ends_with(&a.relative_path))
+    }
+}
+
+/// A CUDA 12.4.1 plan for linux-x86_64 with these archives and nothing else; tests override
+/// the fields they check.
+#[cfg(test)]
+pub(crate) fn test_plan(artifacts: Vec<redist::RedistArtifact>) -> InstallationPlan {
+    InstallationPlan {
+        cuda_version: "12.4.1".to_string(),
+        download_url: String::new(),
+        download_urls: Vec::new(),
+        install_path: PathBuf::from("/opt/cudamgr/12.4.1"),
+        required_driver: None,
+        dependencies: Vec::new(),
+        artifacts,
+        components: Vec::new(),
+        source_dir: None,
+        platform: Some(redist::RedistPlatform::LinuxX86_64),
+        release_label: None,
+    }
 }
 
 /// Dependency information
@@ -38,97 +96,247 @@ pub trait Installer {
     async fn validate_installation(&self, path: &std::path::Path) -> CudaMgrResult<bool>;
 }
 
+/// User choices that shape an installation plan
+#[derive(Debug, Clone, Default)]
+pub struct InstallOptions {
+    /// Explicit redist components (`--components`); must exist in the manifest
+    pub components: Vec<String>,
+    /// Named profile from config (`--profile`)
+    pub profile: Option<String>,
+    /// Install offline from a `cudamgr download` directory (`--from`)
+    pub source_dir: Option<PathBuf>,
+    /// Redist root to use instead of the configured one (`--mirror`)
+    pub mirror: Option<String>,
+    /// Manifest platform to select archives for; detected from the host when unset
+    pub platform: Option<redist::RedistPlatform>,
+    /// Install exactly the archives of this lock (`--locked`) instead of selecting components
+    pub locked: Option<lock::LockFile>,
+}
+
+/// Apply the component selection in `options` to a release's artifacts.
+pub fn select_components(
+    config: &CudaMgrConfig,
+    options: &InstallOptions,
+    artifacts: Vec<redist::RedistArtifact>,
+) -> CudaMgrResult<Vec<redist::RedistArtifact>> {
+    let profile = match &options.profile {
+        Some(name) => config.profile(name)?.to_vec(),
+        None => vec![],
+    };
+    redist::select_artifacts(artifacts, &options.components, &profile)
+}
+
+/// Sorted, de-duplicated component names of a set of artifacts.
+pub fn component_names(artifacts: &[redist::RedistArtifact]) -> Vec<String> {
+    let mut names: Vec<String> = artifacts.iter().map(|a| a.component.clone()).collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
 /// Default installer implementation
-pub struct DefaultInstaller;
+#[derive(Default)]
+pub struct DefaultInstaller {
+    options: InstallOptions,
+    progress: progress::Progress,
+}
+
+impl DefaultInstaller {
+    /// Create an installer that installs every component
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an installer with explicit install options
+    pub fn with_options(options: InstallOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Report download and extraction progress to `progress`
+    pub fn with_progress(mut self, progress: progress::Progress) -> Self {
+        self.progress = progress;
+        self
+    }
+}
 
 impl Installer for DefaultInstaller {
     async fn create_plan(&self, version: &str) -> CudaMgrResult<InstallationPlan> {
+        // Planning writes nothing but the release index cache, so a plan can be shown
+        // (`--dry-run`) without side effects.
         let config = CudaMgrConfig::load()?;
-        config.ensure_directories()?;
+        let source = redist::RedistSource::resolve(self.options.mirror.as_deref(), Some(&config))?;
 
-        let client = reqwest::Client::new();
-        let (full_version, paths) =
-            redist::resolve_version_to_redist_paths(version, &client).await?;
+        let platform = match self.options.platform {
+            Some(p) => p,
+            None => redist::RedistPlatform::detect()?,
+        };
+        // A lock pins the full release, so resolve that rather than the newest patch
+        let version = match &self.options.locked {
+            Some(lock) => {
+                lock_applies(lock, version, platform)?;
+                lock.cuda_version.as_str()
+            }
+            None => version,
+        };
+        let (full_version, manifest, source_dir) = match &self.options.source_dir {
+            Some(dir) => {
+                let release = redist::find_local_release(dir, version)?;
+                (release.full_version, release.manifest, Some(release.dir))
+            }
+            None => {
+                let client = http::client(Some(&config))?;
+                let (full_version, manifest) =
+                    source.resolve_manifest(version, platform, &client).await?;
+                (full_version, manifest, None)
+            }
+        };
+        let artifacts = match &self.options.locked {
+            Some(lock) => lock.check_manifest(&manifest)?,
+            None => {
+                let artifacts = redist::platform_artifacts(&manifest, platform);
+                select_components(&config, &self.options, artifacts)?
+            }
+        };
+        let components = component_names(&artifacts);
 
         let install_path = config.install_dir.join(&full_version);
-        let base = redist::REDIST_INDEX_URL;
-        let download_urls: Vec<String> = paths
+        let download_urls: Vec<String> = artifacts
             .iter()
-            .map(|p| format!("{}{}", base, p))
+            .map(|a| source.artifact_url(&a.relative_path))
             .collect();
         let download_url = download_urls.first().cloned().unwrap_or_default();
+        let required_driver = REGISTRY.min_driver_for(&full_version);
 
         Ok(InstallationPlan {
             cuda_version: full_version,
             download_url,
             download_urls,
             install_path,
-            required_driver: None,
+            required_driver,
             dependencies: vec![],
+            artifacts,
+            components,
+            source_dir,
+            platform: Some(platform),
+            release_label: redist::release_label(&manifest),
         })
     }
 
     async fn execute_plan(&self, plan: &InstallationPlan) -> CudaMgrResult<()> {
-        std::fs::create_dir_all(&plan.install_path).map_err(|e| {
-            CudaMgrError::Install(InstallError::Installation(format!(
-                "Failed to create install directory: {}",
-                e
-            )))
-        })?;
-
         if plan.download_urls.is_empty() {
             return Err(CudaMgrError::Install(InstallError::Installation(
                 "No redist artifacts in plan; cannot install toolkit.".to_string(),
             )));
         }
 
-        let downloader = downloader::PackageDownloader::new();
-        let cache_dir = CudaMgrConfig::load()
-            .map(|c| c.cache_dir)
-            .unwrap_or_else(|_| plan.install_path.join("..").join(".cache").join(&plan.cuda_version));
+        let config = CudaMgrConfig::load().ok();
+        if let Some(config) = &config {
+            config.ensure_directories()?;
+        }
+        let source =
+            redist::RedistSource::resolve(self.options.mirror.as_deref(), config.as_ref())?;
+        let downloader = downloader::PackageDownloader::from_config(config.as_ref())?
+            .with_progress(self.progress.clone());
+        // A locked install is only as good as its hash checks
+        let verify = self.options.locked.is_some()
+            || config.as_ref().map(|c| c.verify_downloads).unwrap_or(true);
+        let auto_cleanup = config.as_ref().map(|c| c.auto_cleanup).unwrap_or(true);
+        let dedupe_after = config.as_ref().is_some_and(|c| c.dedupe_after_install);
+        let concurrency = config
+            .as_ref()
+            .map(|c| c.download_concurrency())
+            .unwrap_or(1);
+        let cache_dir = config.map(|c| c.cache_dir).unwrap_or_else(|| {
+            plan.install_path
+                .join("..")
+                .join(".cache")
+                .join(&plan.cuda_version)
+        });
         std::fs::create_dir_all(&cache_dir).map_err(|e| {
             CudaMgrError::Install(InstallError::Download(format!(
                 "Failed to create cache dir: {}",
                 e
             )))
         })?;
+        space::check(plan, &cache_dir)?;
 
-        let total = plan.download_urls.len();
-        for (i, url) in plan.download_urls.iter().enumerate() {
-            let filename = url
-                .rsplit('/')
-                .next()
-                .unwrap_or("archive")
-                .to_string();
-            let dest_file = cache_dir.join(&filename);
-            eprintln!("  [{}/{}] Downloading {} ...", i + 1, total, filename);
-            downloader.download(url, &dest_file).await?;
-            eprintln!("  [{}/{}] Extracting {} ...", i + 1, total, filename);
-            extract_and_merge(&dest_file, &plan.install_path)?;
-            let _ = std::fs::remove_file(&dest_file);
-        }
+        // Build the tree in a staging directory next to the target and only rename it into
+        // place once every component is in, so a failure never leaves a half-merged install.
+        let cleanup = cleanup::InstallationCleanup::new();
+        cleanup
+            .cleanup_failed_installation(&plan.install_path)
+            .await?;
+        let staging = cleanup::staging_path(&plan.install_path);
+        std::fs::create_dir_all(&staging).map_err(|e| {
+            CudaMgrError::Install(InstallError::Installation(format!(
+                "Failed to create staging directory: {}",
+                e
+            )))
+        })?;
 
-        // If nvcc is still missing (e.g. not in some manifests or wrong layout), install cuda_nvcc explicitly
-        if !nvcc_binary_path(&plan.install_path).exists() {
-            tracing::info!("nvcc not found after install; fetching cuda_nvcc component...");
-            let client = reqwest::Client::new();
-            if let Ok(manifest) = redist::get_redist_manifest(&plan.cuda_version, &client).await {
-                let nvcc_paths =
-                    redist::get_component_paths_from_manifest(&manifest, "cuda_nvcc");
-                for rel in nvcc_paths {
-                    let url = format!("{}{}", redist::REDIST_INDEX_URL, rel);
-                    let filename = url.rsplit('/').next().unwrap_or("nvcc_archive").to_string();
-                    let dest_file = cache_dir.join(&filename);
-                    downloader.download(&url, &dest_file).await?;
-                    extract_and_merge(&dest_file, &plan.install_path)?;
-                    let _ = std::fs::remove_file(&dest_file);
+        let staged = stage_plan(
+            plan,
+            nvcc_fallback(plan, &self.options, &source),
+            &downloader,
+            &staging,
+            &cache_dir,
+            verify,
+            concurrency,
+        )
+        .await
+        .and_then(|staged| {
+            receipt::InstallReceipt::record(
+                &plan.cuda_version,
+                plan.release_label.clone(),
+                staged.components,
+                &staging,
+                &staged.placed,
+            )?
+            .write(&staging)?;
+            validate_staged(&staging)?;
+            Ok(staged.cached_archives)
+        });
+        if let Err(e) = cleanup.remove_temp_files(&cache_dir).await {
+            tracing::warn!("{}", e);
+        }
+        let cached = match staged {
+            Ok(cached) => cached,
+            Err(e) => {
+                if let Err(cleanup_err) = cleanup
+                    .cleanup_failed_installation(&plan.install_path)
+                    .await
+                {
+                    tracing::warn!("{}", cleanup_err);
                 }
+                return Err(e);
             }
-            if !nvcc_binary_path(&plan.install_path).exists() {
-                tracing::warn!(
-                    "nvcc still not found at {} (cuda_nvcc may not be available for this version)",
-                    nvcc_binary_path(&plan.install_path).display()
-                );
+        };
+
+        cleanup.commit_installation(&plan.install_path).await?;
+        // Verified archives stay cached after a failure so a retry doesn't fetch them again;
+        // after a success, auto_cleanup decides.
+        if auto_cleanup {
+            if let Err(e) = cache::ArchiveCache::new(&cache_dir).remove_archives(&cached) {
+                tracing::warn!("{}", e);
+            }
+        }
+
+        if let Some(install_dir) = plan.install_path.parent().filter(|_| dedupe_after) {
+            let options = dedupe::DedupeOptions {
+                focus: Some(plan.install_path.clone()),
+                ..Default::default()
+            };
+            match dedupe::dedupe(install_dir, &options) {
+                Ok(report) if report.files_linked > 0 => self.progress.message(&format!(
+                    "Linked {} files shared with other versions, saved {}",
+                    report.files_linked,
+                    format_bytes(report.bytes_saved)
+                )),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Dedupe after install failed: {}", e),
             }
         }
 
@@ -140,179 +348,513 @@ impl Installer for DefaultInstaller {
         Ok(())
     }
 
-    async fn validate_installation(&self, _path: &std::path::Path) -> CudaMgrResult<bool> {
-        // TODO: Implement installation validation
-        Err(
-            InstallError::Validation("Installation validation not yet implemented".to_string())
-                .into(),
-        )
+    async fn validate_installation(&self, path: &std::path::Path) -> CudaMgrResult<bool> {
+        validator::InstallationValidator::new()
+            .verify_installation(path)
+            .await
     }
 }
 
-/// Expected path to the nvcc binary for a given install path.
-fn nvcc_binary_path(install_path: &Path) -> PathBuf {
-    let bin = install_path.join("bin");
-    #[cfg(windows)]
-    return bin.join("nvcc.exe");
-    #[cfg(not(windows))]
-    return bin.join("nvcc");
+/// What `stage_plan` put together.
+#[derive(Debug, Default)]
+struct StagedInstall {
+    /// Archives from the archive cache the install used
+    cached_archives: Vec<PathBuf>,
+    /// Component archives extracted, in order
+    components: Vec<receipt::ComponentRecord>,
+    /// (component, path relative to the staging directory) for every entry extracted
+    placed: Vec<(String, PathBuf)>,
 }
 
-/// Extract an archive (.zip or .tar.xz) to a temp dir, then merge contents into target.
-/// NVIDIA redist archives have one top-level dir (e.g. *-archive) with bin/, lib/, include/.
-fn extract_and_merge(archive_path: &Path, target: &Path) -> CudaMgrResult<()> {
-    let ext = archive_path
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("");
-    let parent = archive_path
-        .parent()
-        .unwrap_or(Path::new("."));
-    let stem = archive_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("extract");
-    // For .tar.xz the stem is e.g. "cuda_nvcc-...-archive" (one less extension)
-    let extract_dir = parent.join(format!("{}_extract", stem));
-
-    if archive_path.to_string_lossy().ends_with(".zip") {
-        extract_zip(archive_path, &extract_dir)?;
-    } else if ext == "xz" || archive_path.to_string_lossy().ends_with(".tar.xz") {
-        extract_tar_xz(archive_path, &extract_dir)?;
+/// Where to fetch `cuda_nvcc` from when the plan's archives leave no nvcc: nowhere for an
+/// offline install, which has only the saved archives, or a locked one, which installs only
+/// what the lock lists.
+fn nvcc_fallback<'a>(
+    plan: &InstallationPlan,
+    options: &InstallOptions,
+    source: &'a redist::RedistSource,
+) -> Option<&'a redist::RedistSource> {
+    if plan.source_dir.is_some() || options.locked.is_some() {
+        None
     } else {
-        return Err(InstallError::Installation(format!(
-            "Unsupported archive format: {}",
-            archive_path.display()
-        ))
-        .into());
+        Some(source)
     }
+}
 
-    // Merge: find the single top-level dir (e.g. *-archive) and copy its contents into target.
-    // NVIDIA zips can be either: (1) one wrapper dir *-archive with bin/lib/include inside,
-    // or (2) just bin/ (and/or lib/, include/) at root. We must merge so files end up in target/bin etc.
-    let entries: Vec<_> = std::fs::read_dir(&extract_dir)
-        .map_err(|e| {
-            CudaMgrError::Install(InstallError::Installation(format!(
-                "Read extract dir: {}",
-                e
-            )))
-        })?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| {
-            CudaMgrError::Install(InstallError::Installation(format!("Read dir entry: {}", e)))
-        })?;
-
-    let content_root = if entries.len() == 1 {
-        let e = &entries[0];
-        let p = e.path();
-        if p.is_dir() {
-            p
-        } else {
-            extract_dir.clone()
+/// Download every archive in the plan and merge them into `target` (the staging directory).
+/// Archives with a manifest sha256 are fetched into (or reused from) the archive cache.
+/// `nvcc_source` is where `cuda_nvcc` is fetched from if nvcc is still missing afterwards.
+async fn stage_plan(
+    plan: &InstallationPlan,
+    nvcc_source: Option<&redist::RedistSource>,
+    downloader: &downloader::PackageDownloader,
+    target: &Path,
+    cache_dir: &Path,
+    verify: bool,
+    concurrency: usize,
+) -> CudaMgrResult<StagedInstall> {
+    let archive_cache = cache::ArchiveCache::new(cache_dir);
+    let progress = downloader.progress();
+    let total = plan.download_urls.len();
+    let (jobs, results) = match &plan.source_dir {
+        Some(dir) => {
+            let jobs = plan_download_jobs(plan, dir, None);
+            progress.message(&format!(
+                "Verifying {} archives in {}",
+                total,
+                dir.display()
+            ));
+            progress.batch_started(progress::Stage::Verify, jobs.len(), None);
+            let mut results = Vec::with_capacity(jobs.len());
+            for job in &jobs {
+                let name = job.file_name();
+                progress.file_started(progress::Stage::Verify, &name, None, 0);
+                let result = check_local_archive(downloader, job, verify).await;
+                let outcome = match &result {
+                    Ok(()) => progress::FileOutcome::Verified,
+                    Err(e) => progress::FileOutcome::Failed(e.to_string()),
+                };
+                progress.file_finished(&name, &outcome);
+                results.push(result);
+            }
+            progress.batch_finished();
+            (jobs, results)
+        }
+        None => {
+            let jobs = plan_download_jobs(plan, cache_dir, Some(&archive_cache));
+            let cached = jobs
+                .iter()
+                .filter(|j| {
+                    verify
+                        && j.destination.starts_with(archive_cache.root())
+                        && j.destination.is_file()
+                })
+                .count();
+            progress.message(&format!(
+                "Downloading {} archives ({} at a time, {} cached)",
+                total, concurrency, cached
+            ));
+            let results = downloader.fetch_all(&jobs, concurrency, verify).await;
+            (jobs, results)
         }
-    } else {
-        extract_dir.clone()
     };
+    if let Some(e) = results.into_iter().find_map(Result::err) {
+        return Err(e);
+    }
 
-    // If the only top-level entry is "bin", "lib", or "include", merge it into target/bin etc.,
-    // so we don't put nvcc.exe directly in target/.
-    let merge_dest = content_root
-        .file_name()
-        .and_then(|n| n.to_str())
-        .map(|s| {
-            match s {
-                "bin" => target.join("bin"),
-                "lib" => target.join("lib"),
-                "include" => target.join("include"),
-                _ => target.to_path_buf(),
-            }
+    // Extract one at a time, in plan order, so later archives overwrite earlier ones predictably.
+    let archive_bytes = jobs
+        .iter()
+        .map(|j| {
+            std::fs::metadata(&j.destination)
+                .map(|m| m.len())
+                .unwrap_or(0)
         })
-        .unwrap_or_else(|| target.to_path_buf());
-
-    merge_dir_into(&content_root, &merge_dest)?;
-    let _ = std::fs::remove_dir_all(&extract_dir);
-    Ok(())
-}
-
-fn extract_zip(zip_path: &Path, dest: &Path) -> CudaMgrResult<()> {
-    std::fs::create_dir_all(dest).map_err(|e| {
-        CudaMgrError::Install(InstallError::Installation(format!("Create dir: {}", e)))
-    })?;
-    let file = std::fs::File::open(zip_path).map_err(|e| {
-        CudaMgrError::Install(InstallError::Download(format!("Open zip: {}", e)))
-    })?;
-    let mut archive = zip::ZipArchive::new(file).map_err(|e| {
-        CudaMgrError::Install(InstallError::Installation(format!("Invalid zip: {}", e)))
-    })?;
-    for i in 0..archive.len() {
-        let mut entry = archive.by_index(i).map_err(|e| {
-            CudaMgrError::Install(InstallError::Installation(format!("Zip entry: {}", e)))
-        })?;
-        // Normalize path: zip may use backslashes on Windows; use forward slash for correct structure
-        let name = entry.name().replace('\\', "/");
-        let out_path = dest.join(&name);
-        if entry.is_dir() {
-            std::fs::create_dir_all(&out_path).map_err(|e| {
-                CudaMgrError::Install(InstallError::Installation(format!("Mkdir: {}", e)))
-            })?;
+        .sum();
+    progress.batch_started(progress::Stage::Extract, total, Some(archive_bytes));
+    let mut staged = StagedInstall::default();
+    for job in &jobs {
+        let component = job
+            .artifact
+            .as_ref()
+            .map(|a| a.component.clone())
+            .unwrap_or_else(|| job.file_name());
+        let source_url = if plan.source_dir.is_some() {
+            reqwest::Url::from_file_path(&job.destination)
+                .ok()
+                .map(String::from)
         } else {
-            if let Some(p) = out_path.parent() {
-                std::fs::create_dir_all(p).map_err(|e| {
-                    CudaMgrError::Install(InstallError::Installation(format!("Mkdir: {}", e)))
-                })?;
+            Some(job.url.clone())
+        };
+        staged.components.push(receipt::ComponentRecord {
+            name: component.clone(),
+            version: job.artifact.as_ref().and_then(|a| a.version.clone()),
+            source_url,
+            library: None,
+        });
+        let placed = extract_and_merge(&job.destination, cache_dir, target, progress)?;
+        staged
+            .placed
+            .extend(placed.into_iter().map(|p| (component.clone(), p)));
+        if plan.source_dir.is_some() {
+            continue;
+        }
+        if job.destination.starts_with(archive_cache.root()) {
+            archive_cache.touch(&job.destination);
+            staged.cached_archives.push(job.destination.clone());
+        } else {
+            let _ = std::fs::remove_file(&job.destination);
+        }
+    }
+    progress.batch_finished();
+
+    // If nvcc is still missing (e.g. not in some manifests or wrong layout), install cuda_nvcc explicitly
+    let wants_nvcc = plan.components.is_empty() || plan.components.iter().any(|c| c == "cuda_nvcc");
+    let nvcc_missing = wants_nvcc && !nvcc_binary_path(target).exists();
+    if nvcc_missing && nvcc_source.is_none() {
+        tracing::warn!("nvcc not found in the planned archives; skipping network fallback");
+    } else if let Some(source) = nvcc_source.filter(|_| nvcc_missing) {
+        tracing::info!("nvcc not found after install; fetching cuda_nvcc component...");
+        if let Ok(manifest) = source
+            .fetch_manifest(&plan.cuda_version, downloader.client())
+            .await
+        {
+            let platform = match plan.platform {
+                Some(p) => p,
+                None => redist::RedistPlatform::detect()?,
+            };
+            let nvcc_artifacts =
+                redist::get_component_artifacts_from_manifest(&manifest, platform, "cuda_nvcc");
+            for artifact in nvcc_artifacts {
+                let url = source.artifact_url(&artifact.relative_path);
+                let cache_path = archive_cache.path_for(&artifact);
+                let dest_file = cache_path
+                    .clone()
+                    .unwrap_or_else(|| cache_dir.join(artifact.file_name()));
+                downloader
+                    .fetch_artifact(&url, &dest_file, Some(&artifact), verify)
+                    .await?;
+                staged.components.push(receipt::ComponentRecord {
+                    name: artifact.component.clone(),
+                    version: artifact.version.clone(),
+                    source_url: Some(url),
+                    library: None,
+                });
+                let placed = extract_and_merge(&dest_file, cache_dir, target, progress)?;
+                staged
+                    .placed
+                    .extend(placed.into_iter().map(|p| (artifact.component.clone(), p)));
+                if cache_path.is_some() {
+                    staged.cached_archives.push(dest_file);
+                } else {
+                    let _ = std::fs::remove_file(&dest_file);
+                }
             }
-            let mut out = std::fs::File::create(&out_path).map_err(|e| {
-                CudaMgrError::Install(InstallError::Installation(format!("Create file: {}", e)))
-            })?;
-            io::copy(&mut entry, &mut out).map_err(|e| {
-                CudaMgrError::Install(InstallError::Installation(format!("Copy: {}", e)))
-            })?;
+        }
+        if !nvcc_binary_path(target).exists() {
+            tracing::warn!(
+                "nvcc still not found at {} (cuda_nvcc may not be available for this version)",
+                nvcc_binary_path(&plan.install_path).display()
+            );
         }
     }
+    Ok(staged)
+}
+
+/// A lock only serves the version it was made for, on the platform it was made for.
+fn lock_applies(
+    lock: &lock::LockFile,
+    version: &str,
+    platform: redist::RedistPlatform,
+) -> CudaMgrResult<()> {
+    if !lock.matches(version) {
+        return Err(InstallError::Validation(format!(
+            "{} pins CUDA {} ({}), not {}",
+            lock::LOCK_FILE,
+            lock.cuda_version,
+            lock.spec,
+            version
+        ))
+        .into());
+    }
+    if lock.platform != platform {
+        return Err(InstallError::Validation(format!(
+            "{} was made for {}, not {}",
+            lock::LOCK_FILE,
+            lock.platform,
+            platform
+        ))
+        .into());
+    }
     Ok(())
 }
 
-fn extract_tar_xz(archive_path: &Path, dest: &Path) -> CudaMgrResult<()> {
-    std::fs::create_dir_all(dest).map_err(|e| {
-        CudaMgrError::Install(InstallError::Installation(format!("Create dir: {}", e)))
-    })?;
-    let file = std::fs::File::open(archive_path).map_err(|e| {
-        CudaMgrError::Install(InstallError::Download(format!("Open tar.xz: {}", e)))
-    })?;
-    let dec = xz2::read::XzDecoder::new(file);
-    let mut archive = tar::Archive::new(dec);
-    archive.unpack(dest).map_err(|e| {
-        CudaMgrError::Install(InstallError::Installation(format!("Unpack tar: {}", e)))
-    })?;
+/// Sanity check on a staged tree before it replaces the live install.
+fn validate_staged(staging: &Path) -> CudaMgrResult<()> {
+    let has_content = std::fs::read_dir(staging)
+        .map(|mut entries| entries.next().is_some())
+        .unwrap_or(false);
+    if !has_content {
+        return Err(InstallError::Validation(format!(
+            "Staged installation at {} is empty",
+            staging.display()
+        ))
+        .into());
+    }
     Ok(())
 }
 
-/// Recursively copy contents of src into target (merge: existing dirs are merged).
-fn merge_dir_into(src: &Path, target: &Path) -> CudaMgrResult<()> {
-    for entry in std::fs::read_dir(src).map_err(|e| {
-        CudaMgrError::Install(InstallError::Installation(format!("Read dir: {}", e)))
-    })? {
-        let entry = entry.map_err(|e| {
-            CudaMgrError::Install(InstallError::Installation(format!("Dir entry: {}", e)))
-        })?;
-        let path = entry.path();
-        let name = entry.file_name();
-        let dest = target.join(&name);
-        if path.is_dir() {
-            std::fs::create_dir_all(&dest).map_err(|e| {
-                CudaMgrError::Install(InstallError::Installation(format!("Mkdir: {}", e)))
-            })?;
-            merge_dir_into(&path, &dest)?;
-        } else {
-            if let Some(p) = dest.parent() {
-                std::fs::create_dir_all(p).map_err(|e| {
-                    CudaMgrError::Install(InstallError::Installation(format!("Mkdir: {}", e)))
-                })?;
+/// One download job per plan URL, saving archives in `archive_cache` when the manifest
+/// gives a sha256 and directly under `dir` otherwise.
+fn plan_download_jobs(
+    plan: &InstallationPlan,
+    dir: &Path,
+    archive_cache: Option<&cache::ArchiveCache>,
+) -> Vec<downloader::DownloadJob> {
+    plan.download_urls
+        .iter()
+        .map(|url| {
+            let filename = url.rsplit('/').next().unwrap_or("archive");
+            let artifact = plan.artifact_for_url(url).cloned();
+            let cached = archive_cache
+                .zip(artifact.as_ref())
+                .and_then(|(c, a)| c.path_for(a));
+            downloader::DownloadJob {
+                url: url.clone(),
+                destination: cached.unwrap_or_else(|| dir.join(filename)),
+                artifact,
             }
-            std::fs::copy(&path, &dest).map_err(|e| {
-                CudaMgrError::Install(InstallError::Installation(format!("Copy: {}", e)))
-            })?;
+        })
+        .collect()
+}
+
+/// Make sure a saved archive is present and matches its manifest entry. Unlike downloads,
+/// a bad local archive is reported but left in place.
+async fn check_local_archive(
+    downloader: &downloader::PackageDownloader,
+    job: &downloader::DownloadJob,
+    verify: bool,
+) -> CudaMgrResult<()> {
+    if !job.destination.is_file() {
+        return Err(InstallError::PackageNotFound(format!(
+            "{} is missing from {}",
+            job.file_name(),
+            job.destination.parent().unwrap_or(Path::new(".")).display()
+        ))
+        .into());
+    }
+    match (&job.artifact, verify) {
+        (Some(artifact), true) => downloader.check_artifact(&job.destination, artifact).await,
+        _ => Ok(()),
+    }
+}
+
+/// Expected path to the nvcc binary for a given install path.
+fn nvcc_binary_path(install_path: &Path) -> PathBuf {
+    let bin = install_path.join("bin");
+    #[cfg(windows)]
+    return bin.join("nvcc.exe");
+    #[cfg(not(windows))]
+    return bin.join("nvcc");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offline_plan(dir: &Path, sha256: String) -> InstallationPlan {
+        let file = "cuda_cudart-linux-x86_64-12.4.127-archive.tar.xz";
+        let relative_path = format!("cuda_cudart/linux-x86_64/{}", file);
+        let url = format!("{}{}", redist::REDIST_INDEX_URL, relative_path);
+        InstallationPlan {
+            download_url: url.clone(),
+            download_urls: vec![url],
+            install_path: dir.join("install"),
+            components: vec!["cuda_cudart".to_string()],
+            source_dir: Some(dir.to_path_buf()),
+            release_label: Some("12.4.1".to_string()),
+            ..test_plan(vec![redist::RedistArtifact {
+                component: "cuda_cudart".to_string(),
+                version: Some("12.4.127".to_string()),
+                relative_path,
+                sha256: Some(sha256),
+                size: None,
+            }])
         }
     }
-    Ok(())
+
+    #[tokio::test]
+    async fn test_stage_plan_from_local_archives() {
+        let root = std::env::temp_dir().join("cudamgr_offline_stage");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        let archive = root.join("cuda_cudart-linux-x86_64-12.4.127-archive.tar.xz");
+        {
+            let file = std::fs::File::create(&archive).unwrap();
+            let mut builder = tar::Builder::new(xz2::write::XzEncoder::new(file, 1));
+            let mut header = tar::Header::new_gnu();
+            header.set_size(1);
+            header.set_mode(0o644);
+            builder
+                .append_data(
+                    &mut header,
+                    "cuda_cudart-linux-x86_64-12.4.127-archive/include/cuda_runtime.h",
+                    &b"h"[..],
+                )
+                .unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        let sha = downloader::sha256_file(&archive).unwrap();
+        let downloader = downloader::PackageDownloader::new();
+        let staging = root.join("staging");
+        let cache = root.join("cache");
+        std::fs::create_dir_all(&cache).unwrap();
+
+        let bad = offline_plan(&root, "0".repeat(64));
+        assert!(
+            stage_plan(&bad, None, &downloader, &staging, &cache, true, 1)
+                .await
+                .is_err()
+        );
+        assert!(archive.exists(), "a bad local archive must not be deleted");
+
+        let plan = offline_plan(&root, sha);
+        stage_plan(&plan, None, &downloader, &staging, &cache, true, 1)
+            .await
+            .unwrap();
+        assert!(staging.join("include/cuda_runtime.h").exists());
+        assert!(archive.exists(), "local archives are kept after install");
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_stage_plan_caches_archives_by_sha256() {
+        let root = std::env::temp_dir().join("cudamgr_cached_stage");
+        let _ = std::fs::remove_dir_all(&root);
+        let mirror = root.join("mirror");
+        std::fs::create_dir_all(&mirror).unwrap();
+        let archive = mirror.join("cuda_cudart-linux-x86_64-12.4.127-archive.tar.xz");
+        {
+            let file = std::fs::File::create(&archive).unwrap();
+            let mut builder = tar::Builder::new(xz2::write::XzEncoder::new(file, 1));
+            let mut header = tar::Header::new_gnu();
+            header.set_size(1);
+            header.set_mode(0o644);
+            builder
+                .append_data(
+                    &mut header,
+                    "cuda_cudart-linux-x86_64-12.4.127-archive/lib64/libcudart.so",
+                    &b"l"[..],
+                )
+                .unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        let sha = downloader::sha256_file(&archive).unwrap();
+        let url = reqwest::Url::from_file_path(&archive).unwrap().to_string();
+        let mut plan = offline_plan(&root, sha);
+        plan.source_dir = None;
+        plan.download_urls = vec![url.clone()];
+        plan.artifacts[0].relative_path = url;
+        let source = redist::RedistSource::default();
+        let downloader = downloader::PackageDownloader::new();
+        let cache = root.join("cache");
+
+        let staging = root.join("staging");
+        let used = stage_plan(&plan, Some(&source), &downloader, &staging, &cache, true, 1)
+            .await
+            .unwrap();
+        let cached = cache::ArchiveCache::new(&cache)
+            .path_for(&plan.artifacts[0])
+            .unwrap();
+        assert_eq!(used.cached_archives, vec![cached.clone()]);
+        let component = plan.artifacts[0].component.clone();
+        assert_eq!(
+            used.placed,
+            vec![(component, PathBuf::from("lib64/libcudart.so"))]
+        );
+        assert_eq!(used.components[0].version.as_deref(), Some("12.4.127"));
+        assert_eq!(
+            used.components[0].source_url,
+            Some(plan.download_urls[0].clone())
+        );
+        assert!(cached.exists(), "verified archives stay in the cache");
+        assert!(staging.join("lib64/libcudart.so").exists());
+
+        // A second prefix reuses the cached archive even with the mirror gone
+        std::fs::remove_file(&archive).unwrap();
+        let second = root.join("staging2");
+        stage_plan(&plan, Some(&source), &downloader, &second, &cache, true, 1)
+            .await
+            .unwrap();
+        assert!(second.join("lib64/libcudart.so").exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_locked_install_fetches_nothing_outside_the_lock() {
+        let root = std::env::temp_dir().join("cudamgr_locked_stage");
+        let _ = std::fs::remove_dir_all(&root);
+        let mirror = root.join("mirror");
+        std::fs::create_dir_all(&mirror).unwrap();
+        let write_archive = |name: &str, entry: &str| {
+            let archive = mirror.join(format!("{}.tar.xz", name));
+            let file = std::fs::File::create(&archive).unwrap();
+            let mut builder = tar::Builder::new(xz2::write::XzEncoder::new(file, 1));
+            let mut header = tar::Header::new_gnu();
+            header.set_size(1);
+            header.set_mode(0o755);
+            builder
+                .append_data(&mut header, format!("{}/{}", name, entry), &b"x"[..])
+                .unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+            archive
+        };
+        // The locked archive has no bin/nvcc; the live manifest lists a cuda_nvcc that does
+        let cudart = write_archive(
+            "cuda_cudart-linux-x86_64-12.4.127-archive",
+            "lib64/libcudart.so",
+        );
+        let nvcc = write_archive("cuda_nvcc-linux-x86_64-12.4.131-archive", "bin/nvcc");
+        let manifest = serde_json::json!({
+            "cuda_nvcc": {
+                "version": "12.4.131",
+                "linux-x86_64": {
+                    "relative_path": "cuda_nvcc-linux-x86_64-12.4.131-archive.tar.xz",
+                    "sha256": downloader::sha256_file(&nvcc).unwrap(),
+                },
+            },
+        });
+        std::fs::write(mirror.join("redistrib_12.4.1.json"), manifest.to_string()).unwrap();
+        let source =
+            redist::RedistSource::new(reqwest::Url::from_directory_path(&mirror).unwrap().as_str())
+                .unwrap();
+
+        let url = reqwest::Url::from_file_path(&cudart).unwrap().to_string();
+        let mut plan = offline_plan(&root, downloader::sha256_file(&cudart).unwrap());
+        plan.source_dir = None;
+        plan.components = Vec::new();
+        plan.download_urls = vec![url.clone()];
+        plan.artifacts[0].relative_path = url;
+        let locked = InstallOptions {
+            locked: Some(lock::LockFile::from_plan("12.4", &plan).unwrap()),
+            ..InstallOptions::default()
+        };
+        let downloader = downloader::PackageDownloader::new();
+        let cache = root.join("cache");
+
+        assert!(nvcc_fallback(&plan, &locked, &source).is_none());
+        let staging = root.join("staging");
+        let staged = stage_plan(
+            &plan,
+            nvcc_fallback(&plan, &locked, &source),
+            &downloader,
+            &staging,
+            &cache,
+            true,
+            1,
+        )
+        .await
+        .unwrap();
+        let names: Vec<&str> = staged.components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["cuda_cudart"]);
+        assert!(!staging.join("bin/nvcc").exists());
+
+        // Without the lock the same plan falls back to the live cuda_nvcc
+        let unlocked = InstallOptions::default();
+        let staging = root.join("staging_unlocked");
+        let staged = stage_plan(
+            &plan,
+            nvcc_fallback(&plan, &unlocked, &source),
+            &downloader,
+            &staging,
+            &cache,
+            true,
+            1,
+        )
+        .await
+        .unwrap();
+        assert_eq!(staged.components.len(), 2);
+        assert!(staging.join("bin/nvcc").exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
 }
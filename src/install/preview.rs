@@ -0,0 +1,146 @@
+//! Previews for `install --dry-run` and `download --dry-run`.
+//!
+//! A preview is the resolved plan plus what carrying it out would cost: the bytes still to
+//! fetch (archives already in the archive cache or the download directory are reused) and
+//! the space each filesystem needs against what it has free now. Building one reads the
+//! disk but writes nothing.
+
+use crate::error::CudaMgrResult;
+use crate::install::cache::ArchiveCache;
+use crate::install::space::{self, DiskSpace};
+use crate::install::InstallationPlan;
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanPreview {
+    pub plan: InstallationPlan,
+    /// Components whose archive is already on disk and would not be fetched
+    pub reused: Vec<String>,
+    /// Bytes to fetch over the network; zero for `install --from`
+    pub download_bytes: u64,
+    /// Archives without a manifest `size`, left out of the byte counts
+    pub unknown_sizes: usize,
+    /// Space needed on each filesystem written to
+    pub disk: Vec<DiskSpace>,
+}
+
+impl PlanPreview {
+    /// Preview installing `plan`, with archives cached under `cache_dir`.
+    pub fn install(plan: InstallationPlan, cache_dir: &Path) -> CudaMgrResult<Self> {
+        let disk = space::plan_space(&plan, cache_dir)?;
+        Ok(Self::new(plan, cache_dir, None, disk))
+    }
+
+    /// Preview `cudamgr download` of `plan` into `dir`, which replaces the plan's install
+    /// path.
+    pub fn download(
+        mut plan: InstallationPlan,
+        dir: &Path,
+        cache_dir: &Path,
+    ) -> CudaMgrResult<Self> {
+        plan.install_path = dir.to_path_buf();
+        let archive_bytes = plan.artifacts.iter().filter_map(|a| a.size).sum();
+        let disk = vec![DiskSpace::for_dir(dir, archive_bytes)?];
+        Ok(Self::new(plan, cache_dir, Some(dir), disk))
+    }
+
+    fn new(
+        plan: InstallationPlan,
+        cache_dir: &Path,
+        download_dir: Option<&Path>,
+        disk: Vec<DiskSpace>,
+    ) -> Self {
+        let cache = ArchiveCache::new(cache_dir);
+        let mut reused = Vec::new();
+        let mut download_bytes = 0;
+        if plan.source_dir.is_none() {
+            for artifact in &plan.artifacts {
+                let on_disk = cache.lookup(artifact).is_some()
+                    || download_dir.is_some_and(|d| d.join(artifact.file_name()).is_file());
+                if on_disk {
+                    reused.push(artifact.component.clone());
+                } else {
+                    download_bytes += artifact.size.unwrap_or(0);
+                }
+            }
+        }
+        let unknown_sizes = plan.artifacts.iter().filter(|a| a.size.is_none()).count();
+        Self {
+            plan,
+            reused,
+            download_bytes,
+            unknown_sizes,
+            disk,
+        }
+    }
+
+    /// Combined size of every archive in the plan.
+    pub fn archive_bytes(&self) -> u64 {
+        self.plan.artifacts.iter().filter_map(|a| a.size).sum()
+    }
+
+    /// Whether every filesystem has room.
+    pub fn fits(&self) -> bool {
+        self.disk.iter().all(DiskSpace::fits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::install::redist::RedistArtifact;
+    use crate::install::test_plan;
+
+    fn artifact(component: &str, sha: char, size: Option<u64>) -> RedistArtifact {
+        RedistArtifact {
+            component: component.to_string(),
+            version: Some("12.4.127".to_string()),
+            relative_path: format!("{0}/linux-x86_64/{0}-archive.tar.xz", component),
+            sha256: Some(sha.to_string().repeat(64)),
+            size,
+        }
+    }
+
+    #[test]
+    fn test_preview_skips_reused_archives() {
+        let root = std::env::temp_dir().join("cudamgr_preview");
+        let _ = std::fs::remove_dir_all(&root);
+        let cache_dir = root.join("cache");
+        let artifacts = vec![
+            artifact("cuda_cudart", 'a', Some(100)),
+            artifact("cuda_nvcc", 'b', Some(2000)),
+            artifact("libcufft", 'c', None),
+        ];
+        let cached = ArchiveCache::new(&cache_dir)
+            .path_for(&artifacts[0])
+            .unwrap();
+        std::fs::create_dir_all(cached.parent().unwrap()).unwrap();
+        std::fs::write(&cached, "archive").unwrap();
+        let plan = InstallationPlan {
+            install_path: root.join("versions/12.4.1"),
+            required_driver: Some("550".to_string()),
+            ..test_plan(artifacts)
+        };
+
+        let install = PlanPreview::install(plan.clone(), &cache_dir).unwrap();
+        assert_eq!(install.reused, vec!["cuda_cudart".to_string()]);
+        assert_eq!(install.download_bytes, 2000);
+        assert_eq!(install.archive_bytes(), 2100);
+        assert_eq!(install.unknown_sizes, 1);
+        assert!(install.fits());
+        assert!(!root.join("versions").exists());
+
+        let out = root.join("downloads/12.4.1");
+        let download = PlanPreview::download(plan, &out, &cache_dir).unwrap();
+        assert_eq!(download.plan.install_path, out);
+        assert_eq!(download.disk.len(), 1);
+        assert_eq!(download.disk[0].needed_bytes, 2100);
+        assert!(!out.exists());
+
+        let json = serde_json::to_value(&download).unwrap();
+        assert_eq!(json["plan"]["required_driver"], "550");
+        assert_eq!(json["download_bytes"], 2000);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
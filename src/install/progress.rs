@@ -0,0 +1,91 @@
+//! Progress reporting for downloads, archive checks and extraction.
+//!
+//! The downloader and installer describe what they are doing through [`ProgressReporter`];
+//! they never print progress themselves. Files are identified by name, so reports from
+//! parallel downloads can interleave freely. The CLI renders them as progress bars, and
+//! library users get silence unless they plug in their own reporter.
+
+use std::fmt;
+use std::sync::Arc;
+
+/// What is being done to the files of a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Download,
+    /// Checking archives that are already on disk (`install --from`)
+    Verify,
+    Extract,
+}
+
+/// How work on one file ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileOutcome {
+    Downloaded,
+    /// An existing copy (archive cache or earlier download) verified and was used as is
+    Reused,
+    Verified,
+    Extracted,
+    Failed(String),
+}
+
+/// Receives progress events. Every method has an empty default, so a reporter only
+/// implements what it shows. `file_progress` is called for every chunk; keep it cheap.
+pub trait ProgressReporter: Send + Sync {
+    /// A batch of `files` begins; `total_bytes` is their combined size when every size is
+    /// known up front.
+    fn batch_started(&self, _stage: Stage, _files: usize, _total_bytes: Option<u64>) {}
+
+    /// Work on `file` started, or restarted after a retry. `offset` bytes of `total` were
+    /// already done (a resumed download).
+    fn file_started(&self, _stage: Stage, _file: &str, _total: Option<u64>, _offset: u64) {}
+
+    /// `done` bytes of `file` are processed, counting any resumed offset.
+    fn file_progress(&self, _file: &str, _done: u64) {}
+
+    fn file_finished(&self, _file: &str, _outcome: &FileOutcome) {}
+
+    fn batch_finished(&self) {}
+
+    /// A status line that isn't about a single file.
+    fn message(&self, _text: &str) {}
+}
+
+/// Reporter that ignores everything.
+pub struct Silent;
+
+impl ProgressReporter for Silent {}
+
+/// Shared handle to a reporter, cheap to clone into download tasks. Defaults to [`Silent`].
+#[derive(Clone)]
+pub struct Progress(Arc<dyn ProgressReporter>);
+
+impl Progress {
+    pub fn new(reporter: impl ProgressReporter + 'static) -> Self {
+        Self(Arc::new(reporter))
+    }
+
+    /// Wrap a reporter the caller keeps a handle to (e.g. to read what it collected).
+    pub fn from_arc(reporter: Arc<dyn ProgressReporter>) -> Self {
+        Self(reporter)
+    }
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self::new(Silent)
+    }
+}
+
+impl std::ops::Deref for Progress {
+    type Target = dyn ProgressReporter;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Progress")
+    }
+}
@@ -0,0 +1,291 @@
+//! Install receipt: what a cudamgr install put on disk.
+//!
+//! Written into the staging tree as `.cudamgr-receipt.json` before it is committed, so every
+//! install records the manifest release, the component archives it came from, and the files
+//! each one placed with their sizes and sha256 hashes. `verify` checks an install against it
+//! and `owns` maps a file back to its component.
+
+use crate::error::{CudaMgrResult, InstallError};
+use crate::install::downloader::sha256_file;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File name of the receipt inside an install directory.
+pub const RECEIPT_FILE: &str = ".cudamgr-receipt.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InstallReceipt {
+    pub cuda_version: String,
+    /// Manifest `release_label`
+    #[serde(default)]
+    pub release_label: Option<String>,
+    pub installed_at: DateTime<Utc>,
+    pub components: Vec<ComponentRecord>,
+    pub files: Vec<FileRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ComponentRecord {
+    /// Redist component key (e.g. "cuda_nvcc")
+    pub name: String,
+    /// Component version from the manifest (e.g. "12.4.131")
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Where the archive came from: the redist URL, or a `file://` URL for offline installs
+    #[serde(default)]
+    pub source_url: Option<String>,
+    /// Library release the archive belongs to, for components added with `cudamgr add`;
+    /// unset for the toolkit's own
+    #[serde(default)]
+    pub library: Option<LibraryRelease>,
+}
+
+/// Where a companion library's component was resolved, so it can be found again.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LibraryRelease {
+    /// Library key (e.g. "cudnn")
+    pub name: String,
+    /// Full library release whose manifest lists the archive (e.g. "9.1.0")
+    pub version: String,
+    /// Manifest variant (e.g. "cuda12")
+    pub cuda_variant: String,
+    /// Redist root the manifest was read from
+    pub redist_root: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileRecord {
+    /// Path relative to the install directory, `/`-separated
+    pub path: String,
+    /// Component whose archive provided the file
+    pub component: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl InstallReceipt {
+    /// Hash the regular files each component placed under `root`. When two archives ship the
+    /// same path, the later one (which overwrote the file) owns it.
+    pub fn record(
+        cuda_version: &str,
+        release_label: Option<String>,
+        mut components: Vec<ComponentRecord>,
+        root: &Path,
+        placed: &[(String, PathBuf)],
+    ) -> CudaMgrResult<Self> {
+        let mut files: Vec<FileRecord> = Vec::new();
+        for (component, relative) in placed {
+            if !components.iter().any(|c| &c.name == component) {
+                components.push(ComponentRecord {
+                    name: component.clone(),
+                    version: None,
+                    source_url: None,
+                    library: None,
+                });
+            }
+            let path = root.join(relative);
+            let Ok(meta) = std::fs::symlink_metadata(&path) else {
+                continue;
+            };
+            if !meta.is_file() {
+                continue;
+            }
+            let sha256 = sha256_file(&path).map_err(|e| {
+                InstallError::Installation(format!("Hash {}: {}", path.display(), e))
+            })?;
+            let record = FileRecord {
+                path: relative_string(relative),
+                component: component.clone(),
+                size: meta.len(),
+                sha256,
+            };
+            match files.iter_mut().find(|f| f.path == record.path) {
+                Some(existing) => *existing = record,
+                None => files.push(record),
+            }
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Self {
+            cuda_version: cuda_version.to_string(),
+            release_label,
+            installed_at: Utc::now(),
+            components,
+            files,
+        })
+    }
+
+    /// Files under `install_path` that are missing or no longer match their recorded size
+    /// and sha256.
+    pub fn damaged_files(&self, install_path: &Path) -> Vec<&FileRecord> {
+        self.files
+            .iter()
+            .filter(|file| {
+                let path = install_path.join(&file.path);
+                let intact = std::fs::metadata(&path)
+                    .map(|m| m.len() == file.size)
+                    .unwrap_or(false)
+                    && sha256_file(&path).is_ok_and(|h| h.eq_ignore_ascii_case(&file.sha256));
+                !intact
+            })
+            .collect()
+    }
+
+    /// Replace what the receipt says about `components` after they were extracted again:
+    /// their records and every file they placed under `install_path`.
+    pub fn replace_components(
+        &mut self,
+        install_path: &Path,
+        components: Vec<ComponentRecord>,
+        placed: &[(String, PathBuf)],
+    ) -> CudaMgrResult<()> {
+        let fresh = Self::record(
+            &self.cuda_version,
+            None,
+            components.clone(),
+            install_path,
+            placed,
+        )?;
+        for component in components {
+            match self
+                .components
+                .iter_mut()
+                .find(|c| c.name == component.name)
+            {
+                Some(existing) => *existing = component,
+                None => self.components.push(component),
+            }
+        }
+        self.files
+            .retain(|f| !fresh.components.iter().any(|c| c.name == f.component));
+        self.files
+            .retain(|f| !fresh.files.iter().any(|n| n.path == f.path));
+        self.files.extend(fresh.files);
+        self.files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(())
+    }
+
+    /// The component that installed `relative` (`/`-separated, relative to the install).
+    pub fn owner(&self, relative: &str) -> Option<(&FileRecord, Option<&ComponentRecord>)> {
+        let file = self.files.iter().find(|f| f.path == relative)?;
+        let component = self.components.iter().find(|c| c.name == file.component);
+        Some((file, component))
+    }
+
+    /// Bytes each component placed, largest first. Components without files are left out.
+    pub fn component_sizes(&self) -> Vec<(String, u64)> {
+        let mut sizes: Vec<(String, u64)> = Vec::new();
+        for file in &self.files {
+            match sizes.iter_mut().find(|(name, _)| *name == file.component) {
+                Some((_, size)) => *size += file.size,
+                None => sizes.push((file.component.clone(), file.size)),
+            }
+        }
+        sizes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        sizes
+    }
+
+    /// Path of the receipt for an install directory.
+    pub fn path(install_path: &Path) -> PathBuf {
+        install_path.join(RECEIPT_FILE)
+    }
+
+    pub fn write(&self, install_path: &Path) -> CudaMgrResult<()> {
+        let path = Self::path(install_path);
+        let body = serde_json::to_string_pretty(self)
+            .map_err(|e| InstallError::Installation(format!("Serialize install receipt: {}", e)))?;
+        std::fs::write(&path, body)
+            .map_err(|e| InstallError::Installation(format!("Write {}: {}", path.display(), e)))?;
+        Ok(())
+    }
+
+    /// The receipt of an install, or `None` for installs made before receipts existed.
+    pub fn load(install_path: &Path) -> CudaMgrResult<Option<Self>> {
+        let path = Self::path(install_path);
+        let body = match std::fs::read_to_string(&path) {
+            Ok(body) => body,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(
+                    InstallError::Validation(format!("Read {}: {}", path.display(), e)).into(),
+                )
+            }
+        };
+        serde_json::from_str(&body).map(Some).map_err(|e| {
+            InstallError::Validation(format!("Parse {}: {}", path.display(), e)).into()
+        })
+    }
+}
+
+/// `path` as stored in a receipt: relative components joined with `/`.
+pub fn relative_string(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_reload() {
+        let root = std::env::temp_dir().join("cudamgr_receipt");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("bin")).unwrap();
+        std::fs::create_dir_all(root.join("include")).unwrap();
+        std::fs::write(root.join("bin/nvcc"), "nvcc").unwrap();
+        std::fs::write(root.join("include/cuda.h"), "new").unwrap();
+
+        let nvcc = ComponentRecord {
+            name: "cuda_nvcc".to_string(),
+            version: Some("12.4.131".to_string()),
+            source_url: Some("https://example.com/redist/cuda_nvcc.tar.xz".to_string()),
+            library: None,
+        };
+        let placed = vec![
+            ("cuda_nvcc".to_string(), PathBuf::from("bin/nvcc")),
+            ("cuda_nvcc".to_string(), PathBuf::from("include/cuda.h")),
+            ("cuda_cudart".to_string(), PathBuf::from("include/cuda.h")),
+            ("cuda_cudart".to_string(), PathBuf::from("lib64/missing.so")),
+        ];
+        let receipt = InstallReceipt::record(
+            "12.4.1",
+            Some("12.4.1".to_string()),
+            vec![nvcc.clone()],
+            &root,
+            &placed,
+        )
+        .unwrap();
+        let names: Vec<&str> = receipt.components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["cuda_nvcc", "cuda_cudart"]);
+        assert_eq!(receipt.files.len(), 2);
+        assert_eq!(receipt.files[0].path, "bin/nvcc");
+        assert_eq!(receipt.files[0].size, 4);
+        assert_eq!(receipt.files[1].component, "cuda_cudart");
+        assert_eq!(
+            receipt.component_sizes(),
+            vec![("cuda_nvcc".to_string(), 4), ("cuda_cudart".to_string(), 3)]
+        );
+
+        let (file, component) = receipt.owner("bin/nvcc").unwrap();
+        assert_eq!(file.sha256.len(), 64);
+        assert_eq!(component, Some(&nvcc));
+        assert!(receipt.owner("lib64/missing.so").is_none());
+
+        assert!(receipt.damaged_files(&root).is_empty());
+        std::fs::write(root.join("bin/nvcc"), "NVCC").unwrap();
+        let damaged: Vec<&str> = receipt
+            .damaged_files(&root)
+            .iter()
+            .map(|f| f.path.as_str())
+            .collect();
+        assert_eq!(damaged, vec!["bin/nvcc"]);
+
+        assert_eq!(InstallReceipt::load(&root).unwrap(), None);
+        receipt.write(&root).unwrap();
+        assert_eq!(InstallReceipt::load(&root).unwrap(), Some(receipt));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
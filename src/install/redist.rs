@@ -2,131 +2,796 @@
 //!
 //! Uses https://developer.download.nvidia.com/compute/cuda/redist/ manifest (redistrib_X.Y.Z.json)
 //! to get direct download URLs for the current platform. "Download in one go" fetches these files.
+//! The redist root can be swapped for a mirror (see [`RedistSource`]). Short versions are
+//! resolved against the root's directory listing, which is cached under `cache_dir`.
 
-use crate::error::{CudaMgrError, CudaMgrResult, InstallError};
+use crate::config::{CudaMgrConfig, RetryPolicy};
+use crate::error::{
+    ConfigError, CudaMgrError, CudaMgrResult, InstallError, SystemError, VersionError,
+};
+use crate::install::retry::{self, Failure};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// Base URL for NVIDIA CUDA redistributable artifacts.
 pub const REDIST_INDEX_URL: &str = "https://developer.download.nvidia.com/compute/cuda/redist/";
 
-#[cfg(target_os = "windows")]
-const PLATFORM_KEY: &str = "windows-x86_64";
+/// Environment variable that overrides the redist root from config.
+pub const REDIST_URL_ENV: &str = "CUDAMGR_REDIST_URL";
 
-#[cfg(not(target_os = "windows"))]
-const PLATFORM_KEY: &str = "linux-x86_64";
+/// Platform keys NVIDIA publishes redist archives under in `redistrib_X.Y.Z.json`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RedistPlatform {
+    #[serde(rename = "linux-x86_64")]
+    LinuxX86_64,
+    /// Arm64 servers (Grace Hopper, other SBSA-compliant systems)
+    #[serde(rename = "linux-sbsa")]
+    LinuxSbsa,
+    /// Jetson / L4T
+    #[serde(rename = "linux-aarch64")]
+    LinuxAarch64,
+    #[serde(rename = "linux-ppc64le")]
+    LinuxPpc64le,
+    #[serde(rename = "windows-x86_64")]
+    WindowsX86_64,
+}
 
-/// Top-level keys in redistrib_X.Y.Z.json are component names; values are objects
-/// that may contain platform keys like "linux-x86_64" or "windows-x86_64".
-fn extract_platform_artifacts(json: &serde_json::Value) -> Vec<String> {
-    let mut paths = Vec::new();
-    let obj = match json.as_object() {
-        Some(o) => o,
-        None => return paths,
-    };
-    for (key, value) in obj {
-        if key == "release_date" || key == "release_label" || key == "release_product" {
-            continue;
+impl RedistPlatform {
+    pub const ALL: [RedistPlatform; 5] = [
+        RedistPlatform::LinuxX86_64,
+        RedistPlatform::LinuxSbsa,
+        RedistPlatform::LinuxAarch64,
+        RedistPlatform::LinuxPpc64le,
+        RedistPlatform::WindowsX86_64,
+    ];
+
+    /// Manifest key, e.g. "linux-sbsa"
+    pub fn key(self) -> &'static str {
+        match self {
+            RedistPlatform::LinuxX86_64 => "linux-x86_64",
+            RedistPlatform::LinuxSbsa => "linux-sbsa",
+            RedistPlatform::LinuxAarch64 => "linux-aarch64",
+            RedistPlatform::LinuxPpc64le => "linux-ppc64le",
+            RedistPlatform::WindowsX86_64 => "windows-x86_64",
         }
-        let platform = value.get(PLATFORM_KEY);
-        if let Some(art) = platform.and_then(|p| p.get("relative_path")) {
-            if let Some(s) = art.as_str() {
-                paths.push(s.to_string());
-            }
+    }
+
+    /// Platform of the machine cudamgr is running on. Arm64 Linux is `linux-aarch64` on
+    /// Jetson (Tegra) boards and `linux-sbsa` everywhere else.
+    pub fn detect() -> CudaMgrResult<Self> {
+        let machine = machine_arch();
+        Self::from_os_arch(std::env::consts::OS, &machine, is_tegra()).ok_or_else(|| {
+            SystemError::UnsupportedPlatform(format!(
+                "no CUDA redist archives for {} on {}",
+                std::env::consts::OS,
+                machine
+            ))
+            .into()
+        })
+    }
+
+    fn from_os_arch(os: &str, arch: &str, tegra: bool) -> Option<Self> {
+        match (os, arch) {
+            ("windows", "x86_64" | "amd64" | "AMD64") => Some(RedistPlatform::WindowsX86_64),
+            ("linux", "x86_64" | "amd64") => Some(RedistPlatform::LinuxX86_64),
+            ("linux", "aarch64" | "arm64") if tegra => Some(RedistPlatform::LinuxAarch64),
+            ("linux", "aarch64" | "arm64") => Some(RedistPlatform::LinuxSbsa),
+            ("linux", "powerpc64le" | "ppc64le") => Some(RedistPlatform::LinuxPpc64le),
+            _ => None,
         }
     }
-    paths
 }
 
-/// Try to find a full version (X.Y.Z) that has a redist manifest. Tries latest patch first.
-fn resolve_version_to_patch(version: &str) -> Vec<String> {
-    let parts: Vec<&str> = version.split('.').collect();
-    if parts.len() >= 3 {
-        return vec![version.to_string()];
+impl std::fmt::Display for RedistPlatform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.key())
     }
-    if parts.len() != 2 {
-        return vec![];
+}
+
+impl std::str::FromStr for RedistPlatform {
+    type Err = CudaMgrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.key().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                let known: Vec<&str> = Self::ALL.iter().map(|p| p.key()).collect();
+                SystemError::UnsupportedPlatform(format!(
+                    "'{}' (expected one of: {})",
+                    s,
+                    known.join(", ")
+                ))
+                .into()
+            })
     }
-    let (major, minor) = (parts[0], parts[1]);
-    let mut candidates = Vec::new();
-    for patch in (0..=9).rev() {
-        candidates.push(format!("{}.{}.{}", major, minor, patch));
+}
+
+/// Machine architecture as the kernel reports it (`uname -m`), falling back to the
+/// architecture cudamgr was built for.
+fn machine_arch() -> String {
+    #[cfg(unix)]
+    {
+        let uname = std::process::Command::new("uname")
+            .arg("-m")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .map(|arch| arch.trim().to_string())
+            .filter(|arch| !arch.is_empty());
+        if let Some(arch) = uname {
+            return arch;
+        }
     }
-    candidates
+    #[cfg(windows)]
+    {
+        // Reports the OS architecture even for an emulated x86_64 build on Arm64.
+        if let Ok(arch) = std::env::var("PROCESSOR_ARCHITEW6432")
+            .or_else(|_| std::env::var("PROCESSOR_ARCHITECTURE"))
+        {
+            return arch;
+        }
+    }
+    std::env::consts::ARCH.to_string()
 }
 
-/// Fetch manifest JSON for a full version (caller can parse for specific components).
-pub async fn get_redist_manifest(
-    full_version: &str,
-    client: &reqwest::Client,
-) -> CudaMgrResult<serde_json::Value> {
-    let url = format!("{}redistrib_{}.json", REDIST_INDEX_URL, full_version);
-    let body = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| InstallError::Download(format!("Fetch manifest: {}", e)))?
-        .error_for_status()
-        .map_err(|e| InstallError::Download(format!("Manifest HTTP: {}", e)))?
-        .text()
-        .await
-        .map_err(|e| InstallError::Download(format!("Read manifest: {}", e)))?;
-    serde_json::from_str(&body).map_err(|e| {
-        CudaMgrError::Install(InstallError::Download(format!("Parse manifest: {}", e)))
+/// Jetson boards run L4T, which ships `/etc/nv_tegra_release` and a Tegra device tree.
+fn is_tegra() -> bool {
+    Path::new("/etc/nv_tegra_release").exists()
+        || std::fs::read("/proc/device-tree/compatible")
+            .map(|c| String::from_utf8_lossy(&c).contains("nvidia,tegra"))
+            .unwrap_or(false)
+}
+
+/// A single platform archive listed in a redist manifest, with the integrity data NVIDIA publishes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedistArtifact {
+    /// Manifest component key (e.g. "cuda_nvcc")
+    pub component: String,
+    /// Component version from the manifest, if present
+    pub version: Option<String>,
+    /// Path relative to the redist root
+    pub relative_path: String,
+    pub sha256: Option<String>,
+    pub size: Option<u64>,
+}
+
+impl RedistArtifact {
+    /// Archive file name (last segment of the relative path).
+    pub fn file_name(&self) -> &str {
+        self.relative_path
+            .rsplit('/')
+            .next()
+            .unwrap_or(&self.relative_path)
+    }
+}
+
+/// Build an artifact from a component entry, if it has an archive for `platform`.
+fn artifact_for_component(
+    component: &str,
+    value: &serde_json::Value,
+    platform: RedistPlatform,
+) -> Option<RedistArtifact> {
+    artifact_from_entry(component, value, value.get(platform.key())?)
+}
+
+/// Build an artifact from the archive entry (`relative_path`, `sha256`, `size`) of a component.
+fn artifact_from_entry(
+    component: &str,
+    value: &serde_json::Value,
+    platform: &serde_json::Value,
+) -> Option<RedistArtifact> {
+    let relative_path = platform.get("relative_path")?.as_str()?.to_string();
+    // NVIDIA publishes size as a string ("123456"); accept numbers too.
+    let size = platform.get("size").and_then(|s| match s {
+        serde_json::Value::String(s) => s.parse().ok(),
+        other => other.as_u64(),
+    });
+    Some(RedistArtifact {
+        component: component.to_string(),
+        version: value
+            .get("version")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        relative_path,
+        sha256: platform
+            .get("sha256")
+            .and_then(|h| h.as_str())
+            .map(String::from),
+        size,
     })
 }
 
-/// Return relative paths for components whose key contains `component_substring` (e.g. "cuda_nvcc").
-pub fn get_component_paths_from_manifest(
+fn is_metadata_key(key: &str) -> bool {
+    key == "release_date" || key == "release_label" || key == "release_product"
+}
+
+/// Top-level keys in redistrib_X.Y.Z.json are component names; values are objects
+/// that may contain platform keys like "linux-x86_64" or "windows-x86_64".
+pub fn platform_artifacts(
     json: &serde_json::Value,
-    component_substring: &str,
-) -> Vec<String> {
+    platform: RedistPlatform,
+) -> Vec<RedistArtifact> {
     let obj = match json.as_object() {
         Some(o) => o,
         None => return vec![],
     };
-    let mut paths = Vec::new();
-    for (key, value) in obj {
-        if !key.contains(component_substring) {
-            continue;
+    obj.iter()
+        .filter(|(key, _)| !is_metadata_key(key))
+        .filter_map(|(key, value)| artifact_for_component(key, value, platform))
+        .collect()
+}
+
+/// Artifacts for `platform` from a manifest whose components are built per CUDA major
+/// (cuDNN, cuTENSOR, ...): the platform entry holds one archive per `cudaN` variant, listed
+/// in `cuda_variant`. Components with a single archive for the platform are kept as-is.
+pub fn variant_artifacts(
+    json: &serde_json::Value,
+    platform: RedistPlatform,
+    cuda_major: &str,
+) -> Vec<RedistArtifact> {
+    let Some(obj) = json.as_object() else {
+        return vec![];
+    };
+    let variant = format!("cuda{}", cuda_major);
+    obj.iter()
+        .filter(|(key, _)| !is_metadata_key(key))
+        .filter_map(|(key, value)| {
+            let entry = value.get(platform.key())?;
+            if entry.get("relative_path").is_some() {
+                return artifact_from_entry(key, value, entry);
+            }
+            artifact_from_entry(key, value, entry.get(&variant)?)
+        })
+        .collect()
+}
+
+/// The manifest's `release_label` (e.g. "12.4.1"), if present.
+pub fn release_label(json: &serde_json::Value) -> Option<String> {
+    json.get("release_label")
+        .and_then(|l| l.as_str())
+        .map(String::from)
+}
+
+/// File under `cache_dir` holding the last redist index listing.
+pub const INDEX_CACHE_FILE: &str = "redist_index.json";
+
+/// Full versions (`X.Y.Z`) named by `redistrib_X.Y.Z.json` entries in a directory listing,
+/// newest first. Works on NVIDIA's HTML index as well as a plain list of file names.
+pub fn parse_index_listing(listing: &str) -> Vec<String> {
+    let mut versions: Vec<String> = listing
+        .split("redistrib_")
+        .skip(1)
+        .filter_map(|rest| {
+            let version = &rest[..rest.find(".json")?];
+            let parts: Vec<&str> = version.split('.').collect();
+            let valid = parts.len() == 3
+                && parts
+                    .iter()
+                    .all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()));
+            valid.then(|| version.to_string())
+        })
+        .collect();
+    versions.sort_by_key(|v| std::cmp::Reverse(version_key(v)));
+    versions.dedup();
+    versions
+}
+
+fn version_key(version: &str) -> Vec<u32> {
+    version.split('.').filter_map(|p| p.parse().ok()).collect()
+}
+
+/// Index listing saved under `cache_dir`, tagged with the root it came from.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedIndex {
+    root: String,
+    /// Unix seconds
+    fetched_at: u64,
+    versions: Vec<String>,
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Root of a redist tree: NVIDIA's server, an HTTP mirror, or a `file://` share laid out the
+/// same way. Every manifest and archive URL is built from one of these.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RedistSource {
+    root: String,
+    retry: RetryPolicy,
+    /// Where the index listing is cached; `None` fetches it every time
+    index_cache: Option<PathBuf>,
+    index_ttl: Duration,
+}
+
+impl Default for RedistSource {
+    fn default() -> Self {
+        Self {
+            root: REDIST_INDEX_URL.to_string(),
+            retry: RetryPolicy::default(),
+            index_cache: None,
+            index_ttl: Duration::ZERO,
+        }
+    }
+}
+
+impl RedistSource {
+    /// Use `root` as the redist root. Accepts `http://`, `https://` and `file://` URLs.
+    pub fn new(root: &str) -> CudaMgrResult<Self> {
+        let root = root.trim();
+        let scheme_ok = ["http://", "https://", "file://"]
+            .iter()
+            .any(|s| root.len() > s.len() && root[..s.len()].eq_ignore_ascii_case(s));
+        if !scheme_ok {
+            return Err(ConfigError::Environment(format!(
+                "Invalid redist URL '{}': expected an http://, https:// or file:// URL",
+                root
+            ))
+            .into());
+        }
+        let root = if root.ends_with('/') {
+            root.to_string()
+        } else {
+            format!("{}/", root)
+        };
+        Ok(Self {
+            root,
+            ..Self::default()
+        })
+    }
+
+    /// Pick the redist root: `--mirror`, then `CUDAMGR_REDIST_URL`, then `redist_url` in
+    /// config, then NVIDIA's server.
+    pub fn resolve(mirror: Option<&str>, config: Option<&CudaMgrConfig>) -> CudaMgrResult<Self> {
+        let env = std::env::var(REDIST_URL_ENV)
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+        let configured = config.and_then(|c| c.redist_url.as_deref());
+        let source = match mirror.or(env.as_deref()).or(configured) {
+            Some(root) => Self::new(root)?,
+            None => Self::default(),
+        };
+        Ok(match config {
+            Some(c) => source.with_retry(c.network.retry.clone()).with_index_cache(
+                c.cache_dir.join(INDEX_CACHE_FILE),
+                Duration::from_secs(c.network.index_ttl_secs),
+            ),
+            None => source,
+        })
+    }
+
+    /// Retry manifest fetches according to `retry`
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Cache the index listing in `path`, trusting it for `ttl`
+    pub fn with_index_cache(mut self, path: PathBuf, ttl: Duration) -> Self {
+        self.index_cache = Some(path);
+        self.index_ttl = ttl;
+        self
+    }
+
+    /// The root URL, always ending in `/`.
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// URL of `redistrib_X.Y.Z.json` for a full version.
+    pub fn manifest_url(&self, full_version: &str) -> String {
+        self.artifact_url(&manifest_file_name(full_version))
+    }
+
+    /// URL of an archive given its manifest `relative_path`.
+    pub fn artifact_url(&self, relative_path: &str) -> String {
+        format!("{}{}", self.root, relative_path.trim_start_matches('/'))
+    }
+
+    /// Fetch manifest JSON for a full version (caller can parse for specific components).
+    pub async fn fetch_manifest(
+        &self,
+        full_version: &str,
+        client: &reqwest::Client,
+    ) -> CudaMgrResult<serde_json::Value> {
+        let url = self.manifest_url(full_version);
+        let body = match file_url_path(&url) {
+            Some(path) => std::fs::read_to_string(&path).map_err(|e| {
+                InstallError::Download(format!("Read manifest {}: {}", path.display(), e))
+            })?,
+            None => {
+                let what = format!("Fetch of {}", url);
+                retry::run(&self.retry, &what, |_| async {
+                    let response = client
+                        .get(&url)
+                        .send()
+                        .await
+                        .map_err(|e| Failure::from_reqwest("Fetch manifest", &e))?;
+                    if !response.status().is_success() {
+                        return Err(Failure::from_status(&self.retry, &response));
+                    }
+                    response
+                        .text()
+                        .await
+                        .map_err(|e| Failure::from_reqwest("Read manifest", &e))
+                })
+                .await?
+            }
+        };
+        serde_json::from_str(&body).map_err(|e| {
+            CudaMgrError::Install(InstallError::Download(format!("Parse manifest: {}", e)))
+        })
+    }
+
+    /// Every full version published under this root, newest first. A cached listing younger
+    /// than the TTL is used as is; an older one only when the root can't be reached.
+    pub async fn list_versions(&self, client: &reqwest::Client) -> CudaMgrResult<Vec<String>> {
+        Ok(self.load_index(client, true).await?.0)
+    }
+
+    /// The index listing, and whether it came from the cache.
+    async fn load_index(
+        &self,
+        client: &reqwest::Client,
+        use_cache: bool,
+    ) -> CudaMgrResult<(Vec<String>, bool)> {
+        if let Some(dir) = file_url_path(&self.root) {
+            let entries = std::fs::read_dir(&dir).map_err(|e| {
+                InstallError::Download(format!("Read redist directory {}: {}", dir.display(), e))
+            })?;
+            let names: Vec<String> = entries
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .collect();
+            return Ok((parse_index_listing(&names.join("\n")), false));
+        }
+
+        let cached = self.read_index_cache();
+        if use_cache {
+            if let Some(c) = &cached {
+                if unix_now().saturating_sub(c.fetched_at) < self.index_ttl.as_secs() {
+                    return Ok((c.versions.clone(), true));
+                }
+            }
         }
-        let platform = value.get(PLATFORM_KEY);
-        if let Some(art) = platform.and_then(|p| p.get("relative_path")) {
-            if let Some(s) = art.as_str() {
-                paths.push(s.to_string());
+        match self.fetch_index(client).await {
+            Ok(versions) => {
+                self.write_index_cache(&versions);
+                Ok((versions, false))
             }
+            Err(e) => match cached {
+                Some(c) if use_cache => {
+                    tracing::warn!("{}; using the cached redist index", e);
+                    Ok((c.versions, true))
+                }
+                _ => Err(e),
+            },
         }
     }
-    paths
+
+    async fn fetch_index(&self, client: &reqwest::Client) -> CudaMgrResult<Vec<String>> {
+        let what = format!("Fetch of redist index {}", self.root);
+        let listing = retry::run(&self.retry, &what, |_| async {
+            let response = client
+                .get(&self.root)
+                .send()
+                .await
+                .map_err(|e| Failure::from_reqwest("Fetch index", &e))?;
+            if !response.status().is_success() {
+                return Err(Failure::from_status(&self.retry, &response));
+            }
+            response
+                .text()
+                .await
+                .map_err(|e| Failure::from_reqwest("Read index", &e))
+        })
+        .await?;
+        let versions = parse_index_listing(&listing);
+        if versions.is_empty() {
+            return Err(InstallError::Download(format!(
+                "No redistrib_*.json entries in the listing at {} (a mirror must serve a directory index)",
+                self.root
+            ))
+            .into());
+        }
+        Ok(versions)
+    }
+
+    fn read_index_cache(&self) -> Option<CachedIndex> {
+        let body = std::fs::read_to_string(self.index_cache.as_ref()?).ok()?;
+        let cached: CachedIndex = serde_json::from_str(&body).ok()?;
+        (cached.root == self.root).then_some(cached)
+    }
+
+    fn write_index_cache(&self, versions: &[String]) {
+        let Some(path) = &self.index_cache else {
+            return;
+        };
+        let cached = CachedIndex {
+            root: self.root.clone(),
+            fetched_at: unix_now(),
+            versions: versions.to_vec(),
+        };
+        let written = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(path, serde_json::to_vec_pretty(&cached)?));
+        if let Err(e) = written {
+            tracing::debug!("Could not cache redist index at {}: {}", path.display(), e);
+        }
+    }
+
+    /// Resolve a short version to its newest published patch and return the raw manifest,
+    /// which must list at least one artifact for `platform`. A version missing from the index
+    /// is a [`VersionError::NotFound`]; an unreachable root stays a download error.
+    pub async fn resolve_manifest(
+        &self,
+        version: &str,
+        platform: RedistPlatform,
+        client: &reqwest::Client,
+    ) -> CudaMgrResult<(String, serde_json::Value)> {
+        self.resolve_manifest_for("CUDA", version, &platform.to_string(), client, |json| {
+            !platform_artifacts(json, platform).is_empty()
+        })
+        .await
+    }
+
+    /// Resolve a short version of `product` to its newest published patch whose manifest
+    /// passes `usable`; `wanted` names what `usable` looks for (e.g. "linux-x86_64 cuda12")
+    /// in the error when no patch does.
+    pub async fn resolve_manifest_for(
+        &self,
+        product: &str,
+        version: &str,
+        wanted: &str,
+        client: &reqwest::Client,
+        usable: impl Fn(&serde_json::Value) -> bool,
+    ) -> CudaMgrResult<(String, serde_json::Value)> {
+        let matching = |versions: &[String]| -> Vec<String> {
+            versions
+                .iter()
+                .filter(|v| version_matches(v, version))
+                .cloned()
+                .collect()
+        };
+        let (mut versions, from_cache) = self.load_index(client, true).await?;
+        let mut candidates = matching(&versions);
+        if candidates.is_empty() && from_cache {
+            // The release may be newer than the cached listing
+            versions = self.load_index(client, false).await?.0;
+            candidates = matching(&versions);
+        }
+        if candidates.is_empty() {
+            return Err(VersionError::NotFound(format!(
+                "{} {} is not published at {} (newest is {})",
+                product,
+                version,
+                self.root,
+                versions.first().map_or("unknown", String::as_str)
+            ))
+            .into());
+        }
+
+        for full in &candidates {
+            let json = self.fetch_manifest(full, client).await?;
+            if usable(&json) {
+                return Ok((full.clone(), json));
+            }
+        }
+        Err(VersionError::NotFound(format!(
+            "{} {} has no {} archives at {} (checked {})",
+            product,
+            version,
+            wanted,
+            self.root,
+            candidates.join(", ")
+        ))
+        .into())
+    }
+
+    /// Resolve a short version (e.g. "12.6") to a full version (e.g. "12.6.3") that has a
+    /// redist manifest, keeping the manifest sha256/size for each artifact.
+    pub async fn resolve_artifacts(
+        &self,
+        version: &str,
+        platform: RedistPlatform,
+        client: &reqwest::Client,
+    ) -> CudaMgrResult<(String, Vec<RedistArtifact>)> {
+        let (full, json) = self.resolve_manifest(version, platform, client).await?;
+        Ok((full, platform_artifacts(&json, platform)))
+    }
+}
+
+/// Local path for a `file://` URL; `None` for any other scheme.
+pub fn file_url_path(url: &str) -> Option<PathBuf> {
+    let parsed = reqwest::Url::parse(url).ok()?;
+    if parsed.scheme() != "file" {
+        return None;
+    }
+    parsed.to_file_path().ok()
+}
+
+/// Return artifacts for components whose key contains `component_substring` (e.g. "cuda_nvcc").
+pub fn get_component_artifacts_from_manifest(
+    json: &serde_json::Value,
+    platform: RedistPlatform,
+    component_substring: &str,
+) -> Vec<RedistArtifact> {
+    platform_artifacts(json, platform)
+        .into_iter()
+        .filter(|a| a.component.contains(component_substring))
+        .collect()
+}
+
+/// Components each component needs to build or run against. Manifests do not carry this,
+/// so it is kept here; dependencies absent from a given release are skipped.
+const COMPONENT_DEPENDENCIES: &[(&str, &[&str])] = &[
+    (
+        "cuda_nvcc",
+        &["cuda_cudart", "cuda_cccl", "cuda_crt", "libnvvm"],
+    ),
+    ("cuda_nvrtc", &["cuda_cudart"]),
+    ("cuda_cupti", &["cuda_cudart"]),
+    ("libcublas", &["cuda_cudart"]),
+    ("libcufft", &["cuda_cudart"]),
+    ("libcurand", &["cuda_cudart"]),
+    ("libcusparse", &["cuda_cudart", "libnvjitlink"]),
+    (
+        "libcusolver",
+        &["cuda_cudart", "libcublas", "libcusparse", "libnvjitlink"],
+    ),
+    ("libnpp", &["cuda_cudart"]),
+    ("libnvjpeg", &["cuda_cudart"]),
+];
+
+/// Direct dependencies of a component.
+pub fn component_dependencies(component: &str) -> &'static [&'static str] {
+    COMPONENT_DEPENDENCIES
+        .iter()
+        .find(|(name, _)| *name == component)
+        .map(|(_, deps)| *deps)
+        .unwrap_or(&[])
+}
+
+/// Narrow a manifest's artifacts to the requested components plus their dependencies.
+///
+/// `required` names must exist in the manifest; `optional` names (e.g. from a profile, which
+/// spans several releases) and dependencies are skipped when absent. If both lists are empty,
+/// or either contains "*", every artifact is kept. Manifest order is preserved.
+pub fn select_artifacts(
+    artifacts: Vec<RedistArtifact>,
+    required: &[String],
+    optional: &[String],
+) -> CudaMgrResult<Vec<RedistArtifact>> {
+    let wants_all = |list: &[String]| list.iter().any(|c| c == "*");
+    if (required.is_empty() && optional.is_empty()) || wants_all(required) || wants_all(optional) {
+        return Ok(artifacts);
+    }
+
+    let available: HashSet<&str> = artifacts.iter().map(|a| a.component.as_str()).collect();
+    let missing: Vec<&str> = required
+        .iter()
+        .map(String::as_str)
+        .filter(|c| !available.contains(c))
+        .collect();
+    if !missing.is_empty() {
+        let mut known: Vec<&str> = available.iter().copied().collect();
+        known.sort_unstable();
+        return Err(InstallError::PackageNotFound(format!(
+            "Component(s) {} not available in this release for this platform (available: {})",
+            missing.join(", "),
+            known.join(", ")
+        ))
+        .into());
+    }
+
+    let mut selected: HashSet<String> = HashSet::new();
+    let mut queue: Vec<&str> = required
+        .iter()
+        .chain(optional.iter())
+        .map(String::as_str)
+        .collect();
+    while let Some(component) = queue.pop() {
+        if !available.contains(component) || !selected.insert(component.to_string()) {
+            continue;
+        }
+        queue.extend(component_dependencies(component));
+    }
+
+    Ok(artifacts
+        .into_iter()
+        .filter(|a| selected.contains(&a.component))
+        .collect())
+}
+
+/// File name NVIDIA uses for a release manifest (`redistrib_X.Y.Z.json`).
+pub fn manifest_file_name(full_version: &str) -> String {
+    format!("redistrib_{}.json", full_version)
 }
 
-/// Fetch redistrib_X.Y.Z.json and return relative paths for current platform.
-pub async fn get_redist_download_paths(
-    full_version: &str,
-    client: &reqwest::Client,
-) -> CudaMgrResult<Vec<String>> {
-    let json = get_redist_manifest(full_version, client).await?;
-    Ok(extract_platform_artifacts(&json))
+/// A release saved by `cudamgr download`: the manifest plus archives in one directory.
+#[derive(Debug, Clone)]
+pub struct LocalRelease {
+    pub full_version: String,
+    /// Directory holding the manifest and archives
+    pub dir: PathBuf,
+    pub manifest: serde_json::Value,
+}
+
+impl LocalRelease {
+    /// Local path of an artifact's archive.
+    pub fn archive_path(&self, artifact: &RedistArtifact) -> PathBuf {
+        self.dir.join(artifact.file_name())
+    }
 }
 
-/// Resolve a short version (e.g. "12.6") to a full version (e.g. "12.6.3") that has a redist manifest,
-/// and return the list of relative paths to download for the current platform.
-pub async fn resolve_version_to_redist_paths(
-    version: &str,
-    client: &reqwest::Client,
-) -> CudaMgrResult<(String, Vec<String>)> {
-    let candidates = resolve_version_to_patch(version);
-    for full in &candidates {
-        match get_redist_download_paths(full, client).await {
-            Ok(paths) if !paths.is_empty() => return Ok((full.clone(), paths)),
-            Ok(_) => continue,
-            Err(_) => continue,
+/// Find a saved release for `version` in `dir`, which may be a version directory
+/// (`<output_dir>/12.4.1`) or the download output directory containing several.
+/// A short version (`12.4`) picks the newest matching patch. Never touches the network.
+pub fn find_local_release(dir: &Path, version: &str) -> CudaMgrResult<LocalRelease> {
+    let mut search_dirs = vec![dir.to_path_buf()];
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        search_dirs.extend(entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()));
+    }
+
+    let mut best: Option<(Vec<u32>, String, PathBuf)> = None;
+    for search_dir in &search_dirs {
+        let Ok(entries) = std::fs::read_dir(search_dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Some(full) = name
+                .strip_prefix("redistrib_")
+                .and_then(|n| n.strip_suffix(".json"))
+            else {
+                continue;
+            };
+            if !version_matches(full, version) {
+                continue;
+            }
+            let key = version_key(full);
+            if best.as_ref().is_none_or(|(k, _, _)| key > *k) {
+                best = Some((key, full.to_string(), search_dir.clone()));
+            }
         }
     }
-    Err(InstallError::Download(format!(
-        "No redist manifest found for version {} (tried {})",
-        version,
-        candidates.join(", ")
-    ))
-    .into())
+
+    let (_, full_version, release_dir) = best.ok_or_else(|| {
+        InstallError::PackageNotFound(format!(
+            "No saved manifest for CUDA {} in {} (run 'cudamgr download {}' on a connected machine)",
+            version,
+            dir.display(),
+            version
+        ))
+    })?;
+    let path = release_dir.join(manifest_file_name(&full_version));
+    let body = std::fs::read_to_string(&path)
+        .map_err(|e| InstallError::Installation(format!("Read {}: {}", path.display(), e)))?;
+    let manifest = serde_json::from_str(&body)
+        .map_err(|e| InstallError::Installation(format!("Parse {}: {}", path.display(), e)))?;
+    Ok(LocalRelease {
+        full_version,
+        dir: release_dir,
+        manifest,
+    })
+}
+
+/// `12.4` matches any `12.4.z`; a full version must match exactly.
+pub fn version_matches(full: &str, requested: &str) -> bool {
+    if requested.split('.').count() >= 3 {
+        full == requested
+    } else {
+        full.strip_prefix(requested)
+            .is_some_and(|rest| rest.starts_with('.'))
+    }
 }
 
 #[cfg(test)]
@@ -134,10 +799,18 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_resolve_version_to_patch() {
-        let c = resolve_version_to_patch("12.6");
-        assert!(c.contains(&"12.6.3".to_string()));
-        assert_eq!(resolve_version_to_patch("12.6.3"), vec!["12.6.3"]);
+    fn test_parse_index_listing() {
+        let html = r#"<a href="redistrib_12.9.1.json">redistrib_12.9.1.json</a>
+            <a href="redistrib_12.10.0.json">redistrib_12.10.0.json</a>
+            <a href="redistrib_12.9.1.json">redistrib_12.9.1.json</a>
+            <a href="redistrib_11.8.0.json">redistrib_11.8.0.json</a>
+            <a href="redistrib_12.json">bad</a> <a href="redistrib_x.y.z.json">bad</a>
+            <a href="cuda_nvcc/">cuda_nvcc/</a>"#;
+        assert_eq!(
+            parse_index_listing(html),
+            vec!["12.10.0", "12.9.1", "11.8.0"]
+        );
+        assert!(parse_index_listing("<html>nothing here</html>").is_empty());
     }
 
     #[test]
@@ -146,13 +819,343 @@ mod tests {
             "release_label": "12.6.3",
             "cuda_cudart": {
                 "linux-x86_64": { "relative_path": "cuda_cudart/linux-x86_64/foo.tar.xz" },
+                "linux-sbsa": { "relative_path": "cuda_cudart/linux-sbsa/foo.tar.xz" },
                 "windows-x86_64": { "relative_path": "cuda_cudart/windows-x86_64/bar.zip" }
             }
         });
-        let paths = extract_platform_artifacts(&json);
-        #[cfg(target_os = "windows")]
-        assert!(paths.iter().any(|p| p.contains("windows-x86_64")));
-        #[cfg(not(target_os = "windows"))]
-        assert!(paths.iter().any(|p| p.contains("linux-x86_64")));
+        let paths = |platform| -> Vec<String> {
+            platform_artifacts(&json, platform)
+                .into_iter()
+                .map(|a| a.relative_path)
+                .collect()
+        };
+        assert_eq!(
+            paths(RedistPlatform::WindowsX86_64),
+            vec!["cuda_cudart/windows-x86_64/bar.zip"]
+        );
+        assert_eq!(
+            paths(RedistPlatform::LinuxSbsa),
+            vec!["cuda_cudart/linux-sbsa/foo.tar.xz"]
+        );
+        assert!(paths(RedistPlatform::LinuxAarch64).is_empty());
+    }
+
+    #[test]
+    fn test_platform_from_os_arch() {
+        use RedistPlatform::*;
+        assert_eq!(
+            RedistPlatform::from_os_arch("linux", "x86_64", false),
+            Some(LinuxX86_64)
+        );
+        assert_eq!(
+            RedistPlatform::from_os_arch("linux", "aarch64", false),
+            Some(LinuxSbsa)
+        );
+        assert_eq!(
+            RedistPlatform::from_os_arch("linux", "aarch64", true),
+            Some(LinuxAarch64)
+        );
+        assert_eq!(
+            RedistPlatform::from_os_arch("linux", "ppc64le", false),
+            Some(LinuxPpc64le)
+        );
+        assert_eq!(
+            RedistPlatform::from_os_arch("windows", "AMD64", false),
+            Some(WindowsX86_64)
+        );
+        assert_eq!(
+            RedistPlatform::from_os_arch("macos", "aarch64", false),
+            None
+        );
+        assert!(RedistPlatform::detect().is_ok() || cfg!(target_os = "macos"));
+    }
+
+    #[test]
+    fn test_platform_parse() {
+        assert_eq!(
+            "linux-sbsa".parse::<RedistPlatform>().unwrap(),
+            RedistPlatform::LinuxSbsa
+        );
+        for p in RedistPlatform::ALL {
+            assert_eq!(p.key().parse::<RedistPlatform>().unwrap(), p);
+        }
+        assert!("linux-arm".parse::<RedistPlatform>().is_err());
+    }
+
+    #[test]
+    fn test_platform_artifacts_carry_integrity_data() {
+        let json = serde_json::json!({
+            "release_label": "12.6.3",
+            "cuda_nvcc": {
+                "version": "12.6.85",
+                "linux-x86_64": {
+                    "relative_path": "cuda_nvcc/linux-x86_64/nvcc.tar.xz",
+                    "sha256": "abc123",
+                    "size": "4096"
+                },
+                "windows-x86_64": {
+                    "relative_path": "cuda_nvcc/windows-x86_64/nvcc.zip",
+                    "sha256": "def456",
+                    "size": "8192"
+                }
+            }
+        });
+        let artifacts = platform_artifacts(&json, RedistPlatform::LinuxX86_64);
+        assert_eq!(artifacts.len(), 1);
+        let a = &artifacts[0];
+        assert_eq!(a.component, "cuda_nvcc");
+        assert_eq!(a.version.as_deref(), Some("12.6.85"));
+        assert!(a.sha256.is_some());
+        assert!(a.size.is_some());
+        assert!(a.file_name().starts_with("nvcc."));
+    }
+
+    fn manifest_artifacts(components: &[&str]) -> Vec<RedistArtifact> {
+        components
+            .iter()
+            .map(|c| RedistArtifact {
+                component: c.to_string(),
+                version: None,
+                relative_path: format!("{}/linux-x86_64/{}.tar.xz", c, c),
+                sha256: None,
+                size: None,
+            })
+            .collect()
+    }
+
+    fn names(artifacts: &[RedistArtifact]) -> Vec<&str> {
+        artifacts.iter().map(|a| a.component.as_str()).collect()
+    }
+
+    #[test]
+    fn test_variant_artifacts_pick_cuda_major() {
+        let json = serde_json::json!({
+            "release_label": "9.1.0",
+            "release_product": "cudnn",
+            "cudnn": {
+                "version": "9.1.0.70",
+                "linux-x86_64": {
+                    "cuda11": { "relative_path": "cudnn/linux-x86_64/cudnn-9.1.0.70_cuda11-archive.tar.xz" },
+                    "cuda12": {
+                        "relative_path": "cudnn/linux-x86_64/cudnn-9.1.0.70_cuda12-archive.tar.xz",
+                        "sha256": "ab",
+                        "size": "1024"
+                    }
+                },
+                "cuda_variant": ["11", "12"]
+            },
+            "cudnn_samples": {
+                "version": "9.1.0.70",
+                "source": { "relative_path": "cudnn_samples/source/samples.tar.xz" }
+            }
+        });
+        let linux = RedistPlatform::LinuxX86_64;
+        let artifacts = variant_artifacts(&json, linux, "12");
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].component, "cudnn");
+        assert!(artifacts[0]
+            .relative_path
+            .ends_with("_cuda12-archive.tar.xz"));
+        assert_eq!(artifacts[0].size, Some(1024));
+        assert!(variant_artifacts(&json, linux, "10").is_empty());
+        assert!(platform_artifacts(&json, linux).is_empty());
+    }
+
+    #[test]
+    fn test_select_artifacts_resolves_dependencies() {
+        let all = manifest_artifacts(&[
+            "cuda_cccl",
+            "cuda_cudart",
+            "cuda_nvcc",
+            "libcublas",
+            "nsight_compute",
+        ]);
+        let selected = select_artifacts(all, &["cuda_nvcc".to_string()], &[]).unwrap();
+        assert_eq!(
+            names(&selected),
+            vec!["cuda_cccl", "cuda_cudart", "cuda_nvcc"]
+        );
+    }
+
+    #[test]
+    fn test_select_artifacts_required_vs_optional() {
+        let all = manifest_artifacts(&["cuda_cudart", "libcublas"]);
+        assert!(select_artifacts(all.clone(), &["libcufft".to_string()], &[]).is_err());
+
+        let selected = select_artifacts(
+            all.clone(),
+            &[],
+            &["libcublas".to_string(), "libnvjitlink".to_string()],
+        )
+        .unwrap();
+        assert_eq!(names(&selected), vec!["cuda_cudart", "libcublas"]);
+
+        assert_eq!(select_artifacts(all.clone(), &[], &[]).unwrap().len(), 2);
+        assert_eq!(
+            select_artifacts(all, &[], &["*".to_string()])
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_find_local_release_picks_newest_patch() {
+        let root = std::env::temp_dir().join("cudamgr_redist_local");
+        let _ = std::fs::remove_dir_all(&root);
+        for v in ["12.4.0", "12.4.1", "12.6.3"] {
+            let dir = root.join(v);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(
+                dir.join(manifest_file_name(v)),
+                format!("{{\"release_label\": \"{}\"}}", v),
+            )
+            .unwrap();
+        }
+
+        let release = find_local_release(&root, "12.4").unwrap();
+        assert_eq!(release.full_version, "12.4.1");
+        assert_eq!(release.dir, root.join("12.4.1"));
+
+        let release = find_local_release(&root.join("12.4.0"), "12.4.0").unwrap();
+        assert_eq!(release.manifest["release_label"], "12.4.0");
+
+        assert!(find_local_release(&root, "12.5").is_err());
+        assert!(!version_matches("12.40.1", "12.4"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_redist_source_urls() {
+        let default = RedistSource::default();
+        assert_eq!(
+            default.manifest_url("12.4.1"),
+            format!("{}redistrib_12.4.1.json", REDIST_INDEX_URL)
+        );
+
+        let mirror = RedistSource::new("https://mirror.example.com/cuda/redist").unwrap();
+        assert_eq!(mirror.root(), "https://mirror.example.com/cuda/redist/");
+        assert_eq!(
+            mirror.artifact_url("cuda_nvcc/linux-x86_64/nvcc.tar.xz"),
+            "https://mirror.example.com/cuda/redist/cuda_nvcc/linux-x86_64/nvcc.tar.xz"
+        );
+
+        assert!(RedistSource::new("ftp://mirror.example.com/redist").is_err());
+        assert!(RedistSource::new("/mnt/share/redist").is_err());
+    }
+
+    #[test]
+    fn test_redist_source_resolve_prefers_flag_over_config() {
+        let config = CudaMgrConfig {
+            redist_url: Some("https://config.example.com/redist/".to_string()),
+            ..Default::default()
+        };
+        let flag = RedistSource::resolve(Some("file:///srv/redist"), Some(&config)).unwrap();
+        assert_eq!(flag.root(), "file:///srv/redist/");
+        if std::env::var(REDIST_URL_ENV).is_err() {
+            let configured = RedistSource::resolve(None, Some(&config)).unwrap();
+            assert_eq!(configured.root(), "https://config.example.com/redist/");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_manifest_from_file_mirror() {
+        let root = std::env::temp_dir().join("cudamgr_file_mirror");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join(manifest_file_name("12.4.1")),
+            r#"{"release_label": "12.4.1", "cuda_cudart": {"linux-x86_64": {"relative_path": "a.tar.xz"}, "windows-x86_64": {"relative_path": "a.zip"}}}"#,
+        )
+        .unwrap();
+        let url = reqwest::Url::from_directory_path(&root).unwrap();
+        let source = RedistSource::new(url.as_str()).unwrap();
+        assert_eq!(
+            file_url_path(&source.artifact_url("a.zip")),
+            Some(root.join("a.zip"))
+        );
+
+        let client = reqwest::Client::new();
+        let (full, json) = source
+            .resolve_manifest("12.4", RedistPlatform::LinuxX86_64, &client)
+            .await
+            .unwrap();
+        assert_eq!(full, "12.4.1");
+        assert_eq!(json["release_label"], "12.4.1");
+
+        let missing = source
+            .resolve_manifest("12.5", RedistPlatform::LinuxX86_64, &client)
+            .await;
+        assert!(matches!(
+            missing,
+            Err(CudaMgrError::Version(VersionError::NotFound(_)))
+        ));
+        let other_platform = source
+            .resolve_manifest("12.4", RedistPlatform::LinuxSbsa, &client)
+            .await;
+        assert!(matches!(
+            other_platform,
+            Err(CudaMgrError::Version(VersionError::NotFound(_)))
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_index_cache_ttl_and_offline_fallback() {
+        let dir = std::env::temp_dir().join("cudamgr_index_cache");
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = dir.join(INDEX_CACHE_FILE);
+        // Nothing listens on the discard port, so every fetch fails to connect
+        let source = RedistSource::new("http://127.0.0.1:9/redist/")
+            .unwrap()
+            .with_retry(RetryPolicy {
+                max_attempts: 1,
+                ..RetryPolicy::default()
+            })
+            .with_index_cache(cache.clone(), Duration::from_secs(60));
+        let client = reqwest::Client::new();
+        let write_cache = |age: u64| {
+            let cached = CachedIndex {
+                root: source.root().to_string(),
+                fetched_at: unix_now() - age,
+                versions: vec!["12.4.1".to_string(), "12.4.0".to_string()],
+            };
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(&cache, serde_json::to_vec(&cached).unwrap()).unwrap();
+        };
+
+        let unreachable = source.list_versions(&client).await;
+        assert!(matches!(
+            unreachable,
+            Err(CudaMgrError::Install(InstallError::Download(_)))
+        ));
+
+        write_cache(10);
+        assert_eq!(
+            source.list_versions(&client).await.unwrap(),
+            vec!["12.4.1", "12.4.0"]
+        );
+        // A version missing from a fresh cache triggers a refetch, whose failure is reported
+        // as a network problem rather than "not found"
+        let missing = source
+            .resolve_manifest("12.6", RedistPlatform::LinuxX86_64, &client)
+            .await;
+        assert!(matches!(
+            missing,
+            Err(CudaMgrError::Install(InstallError::Download(_)))
+        ));
+
+        write_cache(3600);
+        assert_eq!(source.list_versions(&client).await.unwrap().len(), 2);
+
+        let other_root = RedistSource::new("http://127.0.0.1:9/other/")
+            .unwrap()
+            .with_retry(RetryPolicy {
+                max_attempts: 1,
+                ..RetryPolicy::default()
+            })
+            .with_index_cache(cache, Duration::from_secs(60));
+        assert!(other_root.list_versions(&client).await.is_err());
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
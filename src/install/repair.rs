@@ -0,0 +1,430 @@
+//! Restore missing or modified files of an install from its component archives.
+//!
+//! The receipt says which component placed each file and what it hashed to. Components with
+//! a damaged file are looked up in the release manifest again (a library added with
+//! `cudamgr add` in the manifest of the release the receipt recorded for it), their archives
+//! taken from the archive cache, a `cudamgr download` directory or the redist root, and
+//! re-extracted over the install. Everything else is left alone.
+
+use crate::config::CudaMgrConfig;
+use crate::error::{CudaMgrResult, InstallError};
+use crate::install::extract::extract_and_merge;
+use crate::install::progress::Progress;
+use crate::install::receipt::{self, ComponentRecord, InstallReceipt, LibraryRelease};
+use crate::install::{cache, companion, downloader, redist, InstallOptions};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// What a repair found and fixed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairReport {
+    /// Files that were missing or modified, as recorded in the receipt
+    pub damaged_files: Vec<String>,
+    /// Components extracted again
+    pub components: Vec<String>,
+    /// Damaged files of re-extracted components that their archives no longer contain
+    pub still_damaged: Vec<String>,
+    /// Damaged components left alone because no manifest cudamgr can find lists them
+    pub skipped: Vec<String>,
+}
+
+/// Components owning at least one missing or modified file, in receipt order.
+pub fn damaged_components(receipt: &InstallReceipt, install_path: &Path) -> Vec<String> {
+    let owners: BTreeSet<&str> = receipt
+        .damaged_files(install_path)
+        .into_iter()
+        .map(|f| f.component.as_str())
+        .collect();
+    receipt
+        .components
+        .iter()
+        .filter(|c| owners.contains(c.name.as_str()))
+        .map(|c| c.name.clone())
+        .collect()
+}
+
+/// Repair the install at `install_path`. `options.source_dir` and `options.mirror` pick
+/// where archives come from, as for an install; components from an offline install are
+/// taken from the directory they were installed from when it is still there. Progress goes
+/// to `progress`.
+pub async fn repair(
+    install_path: &Path,
+    options: &InstallOptions,
+    progress: &Progress,
+) -> CudaMgrResult<RepairReport> {
+    let mut receipt = InstallReceipt::load(install_path)?.ok_or_else(|| {
+        InstallError::Validation(format!(
+            "{} has no install receipt; reinstall it with 'cudamgr install <version> --force'",
+            install_path.display()
+        ))
+    })?;
+    let mut report = RepairReport {
+        damaged_files: receipt
+            .damaged_files(install_path)
+            .into_iter()
+            .map(|f| f.path.clone())
+            .collect(),
+        ..RepairReport::default()
+    };
+    let damaged = damaged_components(&receipt, install_path);
+    if damaged.is_empty() {
+        return Ok(report);
+    }
+
+    let config = CudaMgrConfig::load().ok();
+    let source = redist::RedistSource::resolve(options.mirror.as_deref(), config.as_ref())?;
+    let downloader = downloader::PackageDownloader::from_config(config.as_ref())?
+        .with_progress(progress.clone());
+    let verify = config.as_ref().map(|c| c.verify_downloads).unwrap_or(true);
+    let auto_cleanup = config.as_ref().map(|c| c.auto_cleanup).unwrap_or(true);
+    let cache_dir = config
+        .as_ref()
+        .map(|c| c.cache_dir.clone())
+        .unwrap_or_else(|| install_path.join("..").join(".cache"));
+    std::fs::create_dir_all(&cache_dir)
+        .map_err(|e| InstallError::Download(format!("Failed to create cache dir: {}", e)))?;
+    let archive_cache = cache::ArchiveCache::new(&cache_dir);
+
+    // Components added with `cudamgr add` are looked up in their library's manifest, the
+    // rest in the toolkit's.
+    let mut toolkit_damaged = Vec::new();
+    let mut library_damaged: BTreeMap<LibraryRelease, Vec<String>> = BTreeMap::new();
+    for component in receipt
+        .components
+        .iter()
+        .filter(|c| damaged.contains(&c.name))
+    {
+        match &component.library {
+            Some(release) => library_damaged
+                .entry(release.clone())
+                .or_default()
+                .push(component.name.clone()),
+            None => toolkit_damaged.push(component.name.clone()),
+        }
+    }
+    let platform = match options.platform {
+        Some(p) => p,
+        None => redist::RedistPlatform::detect()?,
+    };
+
+    // An offline install records file:// sources; fall back to that directory when neither
+    // --from nor --mirror was given and it still holds the release. (A file:// mirror also
+    // records file:// sources, but its archives sit below the root, away from the manifest.)
+    let manifest_name = redist::manifest_file_name(&receipt.cuda_version);
+    let local_dir = options.source_dir.clone().or_else(|| {
+        if options.mirror.is_some() {
+            return None;
+        }
+        receipt
+            .components
+            .iter()
+            .filter(|c| toolkit_damaged.contains(&c.name))
+            .filter_map(|c| c.source_url.as_deref().and_then(redist::file_url_path))
+            .filter_map(|p| p.parent().map(Path::to_path_buf))
+            .find(|dir| dir.join(&manifest_name).is_file())
+    });
+    let local = match &local_dir {
+        Some(dir) if !toolkit_damaged.is_empty() => {
+            Some(redist::find_local_release(dir, &receipt.cuda_version)?)
+        }
+        _ => None,
+    };
+    let mut work: Vec<(
+        redist::RedistArtifact,
+        Option<String>,
+        Option<LibraryRelease>,
+    )> = Vec::new();
+    if !toolkit_damaged.is_empty() {
+        let manifest = match &local {
+            Some(release) => release.manifest.clone(),
+            None => {
+                source
+                    .fetch_manifest(&receipt.cuda_version, downloader.client())
+                    .await?
+            }
+        };
+        for artifact in redist::platform_artifacts(&manifest, platform)
+            .into_iter()
+            .filter(|a| toolkit_damaged.contains(&a.component))
+        {
+            let url = match &local {
+                Some(_) => None,
+                None => Some(source.artifact_url(&artifact.relative_path)),
+            };
+            work.push((artifact, url, None));
+        }
+    }
+    for (release, names) in &library_damaged {
+        let artifacts =
+            companion::release_artifacts(release, platform, config.as_ref(), downloader.client())
+                .await?;
+        for (artifact, url) in artifacts
+            .into_iter()
+            .filter(|(a, _)| names.contains(&a.component))
+        {
+            work.push((artifact, Some(url), Some(release.clone())));
+        }
+    }
+    // Left alone rather than failing the rest: components no manifest lists any more, and
+    // libraries added before cudamgr recorded where they came from
+    report.skipped = damaged
+        .iter()
+        .filter(|name| !work.iter().any(|(a, _, _)| &a.component == *name))
+        .cloned()
+        .collect();
+
+    let mut records = Vec::new();
+    let mut placed: Vec<(String, PathBuf)> = Vec::new();
+    let mut cached = Vec::new();
+    for (artifact, url, library) in &work {
+        let (archive, source_url) = match (url, &local) {
+            (None, Some(release)) => {
+                let archive = release.archive_path(artifact);
+                if !archive.is_file() {
+                    return Err(InstallError::PackageNotFound(format!(
+                        "{} is missing from {}",
+                        artifact.file_name(),
+                        release.dir.display()
+                    ))
+                    .into());
+                }
+                if verify {
+                    downloader.check_artifact(&archive, artifact).await?;
+                }
+                let url = reqwest::Url::from_file_path(&archive)
+                    .ok()
+                    .map(String::from);
+                (archive, url)
+            }
+            _ => {
+                let url = url
+                    .clone()
+                    .unwrap_or_else(|| source.artifact_url(&artifact.relative_path));
+                let cache_path = archive_cache.path_for(artifact);
+                let archive = cache_path
+                    .clone()
+                    .unwrap_or_else(|| cache_dir.join(artifact.file_name()));
+                downloader
+                    .fetch_artifact(&url, &archive, Some(artifact), verify)
+                    .await?;
+                if cache_path.is_some() {
+                    archive_cache.touch(&archive);
+                    cached.push(archive.clone());
+                }
+                (archive, Some(url))
+            }
+        };
+        let files = extract_and_merge(&archive, &cache_dir, install_path, progress)?;
+        if url.is_some() && !archive.starts_with(archive_cache.root()) {
+            let _ = std::fs::remove_file(&archive);
+        }
+        placed.extend(files.into_iter().map(|p| (artifact.component.clone(), p)));
+        records.push(ComponentRecord {
+            name: artifact.component.clone(),
+            version: artifact.version.clone(),
+            source_url,
+            library: library.clone(),
+        });
+        report.components.push(artifact.component.clone());
+    }
+
+    let restored: BTreeSet<String> = placed
+        .iter()
+        .map(|(_, p)| receipt::relative_string(p))
+        .collect();
+    let skipped = |path: &str| {
+        receipt
+            .owner(path)
+            .is_some_and(|(file, _)| report.skipped.contains(&file.component))
+    };
+    report.still_damaged = report
+        .damaged_files
+        .iter()
+        .filter(|f| !restored.contains(*f) && !skipped(f))
+        .cloned()
+        .collect();
+    receipt.replace_components(install_path, records, &placed)?;
+    receipt.write(install_path)?;
+    if auto_cleanup {
+        if let Err(e) = archive_cache.remove_archives(&cached) {
+            tracing::warn!("{}", e);
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_archive(path: &Path, prefix: &str, files: &[(&str, &str)]) {
+        let file = std::fs::File::create(path).unwrap();
+        let mut builder = tar::Builder::new(xz2::write::XzEncoder::new(file, 1));
+        for (name, body) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(body.len() as u64);
+            header.set_mode(0o644);
+            builder
+                .append_data(&mut header, format!("{}/{}", prefix, name), body.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_repair_restores_damaged_component_from_local_release() {
+        let root = std::env::temp_dir().join("cudamgr_repair");
+        let _ = std::fs::remove_dir_all(&root);
+        let redist_dir = root.join("redist");
+        std::fs::create_dir_all(&redist_dir).unwrap();
+        let platform = redist::RedistPlatform::detect().unwrap();
+        let mut manifest = serde_json::json!({ "release_label": "12.4.1" });
+        for (component, file, body) in [
+            ("cuda_cudart", "include/cuda_runtime.h", "runtime"),
+            ("libcufft", "include/cufft.h", "fft"),
+        ] {
+            let name = format!("{}-{}-12.4.0-archive", component, platform);
+            let archive = redist_dir.join(format!("{}.tar.xz", name));
+            write_archive(&archive, &name, &[(file, body)]);
+            manifest[component] = serde_json::json!({
+                "version": "12.4.0",
+                platform.key(): {
+                    "relative_path": format!("{}/{}/{}.tar.xz", component, platform, name),
+                    "sha256": downloader::sha256_file(&archive).unwrap(),
+                },
+            });
+        }
+        std::fs::write(
+            redist_dir.join("redistrib_12.4.1.json"),
+            manifest.to_string(),
+        )
+        .unwrap();
+
+        let install = root.join("versions/12.4.1");
+        std::fs::create_dir_all(install.join("include")).unwrap();
+        std::fs::write(install.join("include/cuda_runtime.h"), "runtime").unwrap();
+        std::fs::write(install.join("include/cufft.h"), "fft").unwrap();
+        let placed = vec![
+            (
+                "cuda_cudart".to_string(),
+                PathBuf::from("include/cuda_runtime.h"),
+            ),
+            ("libcufft".to_string(), PathBuf::from("include/cufft.h")),
+        ];
+        InstallReceipt::record("12.4.1", None, Vec::new(), &install, &placed)
+            .unwrap()
+            .write(&install)
+            .unwrap();
+
+        let options = InstallOptions {
+            source_dir: Some(redist_dir.clone()),
+            ..InstallOptions::default()
+        };
+        let clean = repair(&install, &options, &Progress::default())
+            .await
+            .unwrap();
+        assert_eq!(clean, RepairReport::default());
+
+        std::fs::remove_file(install.join("include/cufft.h")).unwrap();
+        let report = repair(&install, &options, &Progress::default())
+            .await
+            .unwrap();
+        assert_eq!(report.damaged_files, vec!["include/cufft.h".to_string()]);
+        assert_eq!(report.components, vec!["libcufft".to_string()]);
+        assert!(report.still_damaged.is_empty());
+        assert_eq!(
+            std::fs::read_to_string(install.join("include/cufft.h")).unwrap(),
+            "fft"
+        );
+        let receipt = InstallReceipt::load(&install).unwrap().unwrap();
+        let (_, component) = receipt.owner("include/cufft.h").unwrap();
+        assert_eq!(component.unwrap().version.as_deref(), Some("12.4.0"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[tokio::test]
+    async fn test_repair_resolves_library_components_from_their_release() {
+        let root = std::env::temp_dir().join("cudamgr_repair_library");
+        let _ = std::fs::remove_dir_all(&root);
+        let cudnn_root = root.join("cudnn");
+        std::fs::create_dir_all(cudnn_root.join("cudnn")).unwrap();
+        let platform = redist::RedistPlatform::detect().unwrap();
+        let name = format!("cudnn-{}-9.1.0.70_cuda12-archive", platform);
+        let archive = cudnn_root.join(format!("cudnn/{}.tar.xz", name));
+        write_archive(&archive, &name, &[("include/cudnn.h", "cudnn")]);
+        let manifest = serde_json::json!({
+            "release_label": "9.1.0",
+            "cudnn": {
+                "version": "9.1.0.70",
+                platform.key(): {
+                    "cuda12": {
+                        "relative_path": format!("cudnn/{}.tar.xz", name),
+                        "sha256": downloader::sha256_file(&archive).unwrap(),
+                    },
+                },
+            },
+        });
+        std::fs::write(
+            cudnn_root.join("redistrib_9.1.0.json"),
+            manifest.to_string(),
+        )
+        .unwrap();
+
+        let install = root.join("versions/12.4.1");
+        std::fs::create_dir_all(install.join("include")).unwrap();
+        std::fs::write(install.join("include/cudnn.h"), "cudnn").unwrap();
+        std::fs::write(install.join("include/nccl.h"), "nccl").unwrap();
+        let release = LibraryRelease {
+            name: "cudnn".to_string(),
+            version: "9.1.0".to_string(),
+            cuda_variant: "cuda12".to_string(),
+            redist_root: reqwest::Url::from_directory_path(&cudnn_root)
+                .unwrap()
+                .to_string(),
+        };
+        let components = vec![ComponentRecord {
+            name: "cudnn".to_string(),
+            version: Some("9.1.0.70".to_string()),
+            source_url: None,
+            library: Some(release.clone()),
+        }];
+        // nccl stands for a library added before the release was recorded
+        let placed = vec![
+            ("cudnn".to_string(), PathBuf::from("include/cudnn.h")),
+            ("nccl".to_string(), PathBuf::from("include/nccl.h")),
+        ];
+        InstallReceipt::record("12.4.1", None, components, &install, &placed)
+            .unwrap()
+            .write(&install)
+            .unwrap();
+
+        std::fs::remove_file(install.join("include/cudnn.h")).unwrap();
+        std::fs::remove_file(install.join("include/nccl.h")).unwrap();
+        // The toolkit's own manifest doesn't list it either
+        let cuda_dir = root.join("redist");
+        std::fs::create_dir_all(&cuda_dir).unwrap();
+        std::fs::write(
+            cuda_dir.join("redistrib_12.4.1.json"),
+            r#"{ "release_label": "12.4.1" }"#,
+        )
+        .unwrap();
+        let options = InstallOptions {
+            source_dir: Some(cuda_dir),
+            ..InstallOptions::default()
+        };
+        let report = repair(&install, &options, &Progress::default())
+            .await
+            .unwrap();
+        assert_eq!(report.components, vec!["cudnn".to_string()]);
+        assert_eq!(report.skipped, vec!["nccl".to_string()]);
+        assert!(report.still_damaged.is_empty());
+        assert_eq!(
+            std::fs::read_to_string(install.join("include/cudnn.h")).unwrap(),
+            "cudnn"
+        );
+        let receipt = InstallReceipt::load(&install).unwrap().unwrap();
+        let (_, component) = receipt.owner("include/cudnn.h").unwrap();
+        assert_eq!(component.unwrap().library.as_ref(), Some(&release));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,216 @@
+//! Retrying network operations.
+//!
+//! An operation reports each failed attempt as a [`Failure`] saying whether it is worth
+//! retrying; [`run`] waits with exponential backoff (or the server's `Retry-After`) between
+//! attempts and turns the last failure into an [`InstallError::Download`] with the attempt count.
+
+use crate::config::RetryPolicy;
+use crate::error::{CudaMgrResult, InstallError};
+use std::future::Future;
+use std::time::Duration;
+
+/// Why one attempt failed.
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub message: String,
+    pub retryable: bool,
+    /// Delay requested by the server (`Retry-After`)
+    pub retry_after: Option<Duration>,
+}
+
+impl Failure {
+    /// A transient failure (reset connection, timeout, 503, stalled stream)
+    pub fn retryable(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            retryable: true,
+            retry_after: None,
+        }
+    }
+
+    /// A failure that another attempt will not fix (404, disk full, bad URL)
+    pub fn fatal(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            retryable: false,
+            retry_after: None,
+        }
+    }
+
+    /// Classify a reqwest error: connection, timeout and body errors are transient.
+    pub fn from_reqwest(context: &str, e: &reqwest::Error) -> Self {
+        let message = format!("{}: {}", context, e);
+        if e.is_builder() || e.is_redirect() {
+            Self::fatal(message)
+        } else {
+            Self::retryable(message)
+        }
+    }
+
+    /// Failure for an error status; retryable if the policy lists it.
+    pub fn from_status(policy: &RetryPolicy, response: &reqwest::Response) -> Self {
+        let status = response.status();
+        let message = format!("HTTP {} from {}", status, response.url());
+        if policy.retry_statuses.contains(&status.as_u16()) {
+            Self {
+                retry_after: retry_after(response.headers()),
+                ..Self::retryable(message)
+            }
+        } else {
+            Self::fatal(message)
+        }
+    }
+}
+
+impl From<std::io::Error> for Failure {
+    fn from(e: std::io::Error) -> Self {
+        Self::fatal(e.to_string())
+    }
+}
+
+/// Parse `Retry-After` as either delta-seconds or an HTTP date.
+pub fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let when = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delta = when.signed_duration_since(chrono::Utc::now());
+    Some(delta.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Delay before retry number `retry` (1-based): `initial * 2^(retry-1)`, capped.
+pub fn backoff(policy: &RetryPolicy, retry: u32) -> Duration {
+    let factor = 1u64 << retry.saturating_sub(1).min(20);
+    let ms = policy.initial_backoff_ms.saturating_mul(factor);
+    Duration::from_millis(ms.min(policy.max_backoff_ms))
+}
+
+/// Run `op` until it succeeds, fails with a non-retryable error, or runs out of attempts.
+/// `op` receives the 1-based attempt number.
+pub async fn run<T, F, Fut>(policy: &RetryPolicy, what: &str, mut op: F) -> CudaMgrResult<T>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<T, Failure>>,
+{
+    let max_attempts = policy.max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        let failure = match op(attempt).await {
+            Ok(value) => return Ok(value),
+            Err(failure) => failure,
+        };
+        if !failure.retryable || attempt >= max_attempts {
+            return Err(InstallError::Download(format!(
+                "{} failed after {} attempt{}: {}",
+                what,
+                attempt,
+                if attempt == 1 { "" } else { "s" },
+                failure.message
+            ))
+            .into());
+        }
+        let max = Duration::from_millis(policy.max_backoff_ms);
+        let delay = failure
+            .retry_after
+            .map(|d| d.min(max))
+            .unwrap_or_else(|| backoff(policy, attempt));
+        tracing::warn!(
+            "{} failed (attempt {}/{}): {}; retrying in {:.1}s",
+            what,
+            attempt,
+            max_attempts,
+            failure.message,
+            delay.as_secs_f64()
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 5,
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let policy = RetryPolicy {
+            initial_backoff_ms: 500,
+            max_backoff_ms: 3_000,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(backoff(&policy, 1), Duration::from_millis(500));
+        assert_eq!(backoff(&policy, 2), Duration::from_millis(1_000));
+        assert_eq!(backoff(&policy, 3), Duration::from_millis(2_000));
+        assert_eq!(backoff(&policy, 4), Duration::from_millis(3_000));
+        assert_eq!(backoff(&policy, 64), Duration::from_millis(3_000));
+    }
+
+    #[test]
+    fn test_retry_after_parsing() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+
+        headers.insert(
+            reqwest::header::RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(retry_after(&headers), Some(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_run_retries_transient_failures() {
+        let calls = AtomicU32::new(0);
+        let value = run(&fast_policy(3), "Fetch", |attempt| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if attempt < 3 {
+                    Err(Failure::retryable("connection reset"))
+                } else {
+                    Ok(attempt)
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(value, 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_run_reports_attempts_and_stops_on_fatal() {
+        let err = run(&fast_policy(3), "Fetch manifest", |_| async {
+            Err::<(), _>(Failure::retryable("HTTP 503"))
+        })
+        .await
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Fetch manifest failed after 3 attempts: HTTP 503"));
+
+        let calls = AtomicU32::new(0);
+        let err = run(&fast_policy(3), "Fetch", |_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err::<(), _>(Failure::fatal("HTTP 404")) }
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(err.to_string().contains("after 1 attempt: HTTP 404"));
+    }
+}
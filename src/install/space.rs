@@ -0,0 +1,313 @@
+//! Pre-install disk space check.
+//!
+//! Archives are downloaded into `cache_dir`, unpacked there one at a time, and merged into a
+//! staging directory next to the install path. The two may be on different filesystems, so
+//! each gets its own estimate from the manifest `size` fields.
+
+use crate::error::{CudaMgrResult, InstallError};
+use crate::install::cache::ArchiveCache;
+use crate::install::InstallationPlan;
+use crate::system::storage::{format_bytes, StorageInfo};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Unpacked size relative to the `.tar.xz`/`.zip` archive, used when estimating extraction.
+/// CUDA's redist archives are mostly shared libraries and typically expand 2.5-3x.
+pub const EXTRACTION_RATIO: u64 = 3;
+
+/// Bytes an install needs on each filesystem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SpaceRequirement {
+    /// Unpacked toolkit in the staging directory and, once committed, the install path
+    pub install_bytes: u64,
+    /// Archives fetched into the cache
+    pub download_bytes: u64,
+    /// Downloaded archives plus the scratch directory of the largest one being unpacked
+    pub cache_bytes: u64,
+    /// Archives in the plan without a manifest `size`, left out of the estimate
+    pub unknown_sizes: usize,
+}
+
+impl SpaceRequirement {
+    /// Estimate from the plan's manifest sizes. Archives already in `cache` and offline
+    /// installs (`source_dir`), which read archives in place, download nothing, so for them
+    /// only the extraction scratch counts against the cache.
+    pub fn for_plan(plan: &InstallationPlan, cache: &ArchiveCache) -> Self {
+        let sizes: Vec<u64> = plan.artifacts.iter().filter_map(|a| a.size).collect();
+        let archives: u64 = sizes.iter().sum();
+        let largest = sizes.iter().copied().max().unwrap_or(0);
+        let downloaded = if plan.source_dir.is_some() {
+            0
+        } else {
+            plan.artifacts
+                .iter()
+                .filter(|a| cache.lookup(a).is_none())
+                .filter_map(|a| a.size)
+                .sum()
+        };
+        Self {
+            install_bytes: archives * EXTRACTION_RATIO,
+            download_bytes: downloaded,
+            cache_bytes: downloaded + largest * EXTRACTION_RATIO,
+            unknown_sizes: plan.artifacts.len() - sizes.len(),
+        }
+    }
+
+    /// Need and free space per filesystem. On a shared filesystem the unpacked scratch is
+    /// renamed into staging rather than copied, so only the archives come on top of the
+    /// install.
+    pub fn per_filesystem(
+        &self,
+        install_dir: &Path,
+        install_free: u64,
+        cache_dir: &Path,
+        cache_free: u64,
+        same_filesystem: bool,
+    ) -> Vec<DiskSpace> {
+        if same_filesystem {
+            return vec![DiskSpace {
+                paths: vec![install_dir.to_path_buf(), cache_dir.to_path_buf()],
+                needed_bytes: self.install_bytes + self.download_bytes,
+                free_bytes: install_free,
+            }];
+        }
+        vec![
+            DiskSpace {
+                paths: vec![install_dir.to_path_buf()],
+                needed_bytes: self.install_bytes,
+                free_bytes: install_free,
+            },
+            DiskSpace {
+                paths: vec![cache_dir.to_path_buf()],
+                needed_bytes: self.cache_bytes,
+                free_bytes: cache_free,
+            },
+        ]
+    }
+
+    /// Describe each filesystem that is short on room; empty when everything fits.
+    pub fn shortfalls(
+        &self,
+        install_dir: &Path,
+        install_free: u64,
+        cache_dir: &Path,
+        cache_free: u64,
+        same_filesystem: bool,
+    ) -> Vec<String> {
+        self.per_filesystem(
+            install_dir,
+            install_free,
+            cache_dir,
+            cache_free,
+            same_filesystem,
+        )
+        .iter()
+        .filter(|d| !d.fits())
+        .map(DiskSpace::shortfall)
+        .collect()
+    }
+}
+
+/// What one filesystem has to hold.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiskSpace {
+    /// Directories on this filesystem that the work writes to
+    pub paths: Vec<PathBuf>,
+    pub needed_bytes: u64,
+    pub free_bytes: u64,
+}
+
+impl DiskSpace {
+    /// `needed_bytes` for `dir`, against what its filesystem has free now.
+    pub fn for_dir(dir: &Path, needed_bytes: u64) -> CudaMgrResult<Self> {
+        Ok(Self {
+            paths: vec![dir.to_path_buf()],
+            needed_bytes,
+            free_bytes: StorageInfo::available_bytes(dir)?,
+        })
+    }
+
+    pub fn fits(&self) -> bool {
+        self.needed_bytes <= self.free_bytes
+    }
+
+    /// "X needs 3.1 GB but has 1.2 GB free"
+    pub fn shortfall(&self) -> String {
+        let needed = format_bytes(self.needed_bytes);
+        let free = format_bytes(self.free_bytes);
+        match self.paths.as_slice() {
+            [one] => format!("{} needs {} but has {} free", one.display(), needed, free),
+            paths => format!(
+                "{} share a filesystem that needs {} but has {} free",
+                paths
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" and "),
+                needed,
+                free
+            ),
+        }
+    }
+}
+
+/// Space the install of `plan` needs on each filesystem, with what is free there now.
+pub fn plan_space(plan: &InstallationPlan, cache_dir: &Path) -> CudaMgrResult<Vec<DiskSpace>> {
+    let install_dir = &plan.install_path;
+    Ok(
+        SpaceRequirement::for_plan(plan, &ArchiveCache::new(cache_dir)).per_filesystem(
+            install_dir,
+            StorageInfo::available_bytes(install_dir)?,
+            cache_dir,
+            StorageInfo::available_bytes(cache_dir)?,
+            StorageInfo::same_filesystem(install_dir, cache_dir),
+        ),
+    )
+}
+
+/// Fail before anything is downloaded when the install or cache filesystem is too small.
+pub fn check(plan: &InstallationPlan, cache_dir: &Path) -> CudaMgrResult<()> {
+    let required = SpaceRequirement::for_plan(plan, &ArchiveCache::new(cache_dir));
+    if required.unknown_sizes > 0 {
+        tracing::warn!(
+            "{} archive(s) have no size in the manifest; disk space estimate is incomplete",
+            required.unknown_sizes
+        );
+    }
+    let install_dir = &plan.install_path;
+    let shortfalls = required.shortfalls(
+        install_dir,
+        StorageInfo::available_bytes(install_dir)?,
+        cache_dir,
+        StorageInfo::available_bytes(cache_dir)?,
+        StorageInfo::same_filesystem(install_dir, cache_dir),
+    );
+    if shortfalls.is_empty() {
+        return Ok(());
+    }
+    Err(InstallError::Installation(format!(
+        "Not enough disk space for CUDA {}: {}. Free some space or point install_dir/cache_dir \
+         in the config at a larger disk.",
+        plan.cuda_version,
+        shortfalls.join("; ")
+    ))
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::install::redist::RedistArtifact;
+    use crate::install::test_plan;
+    use std::path::PathBuf;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn plan(sizes: &[Option<u64>], source_dir: Option<PathBuf>) -> InstallationPlan {
+        let artifacts = sizes
+            .iter()
+            .enumerate()
+            .map(|(i, size)| RedistArtifact {
+                component: format!("c{}", i),
+                version: None,
+                relative_path: format!("c{}.tar.xz", i),
+                sha256: None,
+                size: *size,
+            })
+            .collect();
+        InstallationPlan {
+            source_dir,
+            ..test_plan(artifacts)
+        }
+    }
+
+    #[test]
+    fn test_requirement_from_manifest_sizes() {
+        let empty = ArchiveCache::new(&std::env::temp_dir().join("cudamgr_space_no_cache"));
+        let req = SpaceRequirement::for_plan(
+            &plan(&[Some(100 * MB), Some(300 * MB), None], None),
+            &empty,
+        );
+        assert_eq!(req.install_bytes, 400 * MB * EXTRACTION_RATIO);
+        assert_eq!(req.download_bytes, 400 * MB);
+        assert_eq!(req.cache_bytes, 400 * MB + 300 * MB * EXTRACTION_RATIO);
+        assert_eq!(req.unknown_sizes, 1);
+
+        let offline = SpaceRequirement::for_plan(
+            &plan(
+                &[Some(100 * MB), Some(300 * MB)],
+                Some(PathBuf::from("/media/redist")),
+            ),
+            &empty,
+        );
+        assert_eq!(offline.download_bytes, 0);
+        assert_eq!(offline.cache_bytes, 300 * MB * EXTRACTION_RATIO);
+    }
+
+    #[test]
+    fn test_cached_archives_are_not_downloaded_again() {
+        let dir = std::env::temp_dir().join("cudamgr_space_cached");
+        let _ = std::fs::remove_dir_all(&dir);
+        let cache = ArchiveCache::new(&dir);
+        let mut plan = plan(&[Some(100 * MB), Some(300 * MB)], None);
+        for (artifact, sha) in plan.artifacts.iter_mut().zip(['a', 'b']) {
+            artifact.sha256 = Some(sha.to_string().repeat(64));
+        }
+        let cached = cache.path_for(&plan.artifacts[1]).unwrap();
+        std::fs::create_dir_all(cached.parent().unwrap()).unwrap();
+        std::fs::write(&cached, "archive").unwrap();
+
+        let req = SpaceRequirement::for_plan(&plan, &cache);
+        assert_eq!(req.download_bytes, 100 * MB);
+        // Still unpacked from the cache, through the scratch directory
+        assert_eq!(req.install_bytes, 400 * MB * EXTRACTION_RATIO);
+        assert_eq!(req.cache_bytes, 100 * MB + 300 * MB * EXTRACTION_RATIO);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_shortfalls_per_filesystem() {
+        let req = SpaceRequirement {
+            install_bytes: 3000 * MB,
+            download_bytes: 500 * MB,
+            cache_bytes: 1500 * MB,
+            unknown_sizes: 0,
+        };
+        let (install, cache) = (Path::new("/opt/cudamgr"), Path::new("/var/cache/cudamgr"));
+
+        assert!(req
+            .shortfalls(install, 4000 * MB, cache, 2000 * MB, false)
+            .is_empty());
+        let short = req.shortfalls(install, 4000 * MB, cache, 1000 * MB, false);
+        assert_eq!(short.len(), 1);
+        assert!(short[0].starts_with("/var/cache/cudamgr needs"));
+        assert_eq!(
+            req.shortfalls(install, 100 * MB, cache, 100 * MB, false)
+                .len(),
+            2
+        );
+
+        // Shared: install plus the archives, with the scratch renamed into staging
+        assert!(req
+            .shortfalls(install, 3500 * MB, cache, 3500 * MB, true)
+            .is_empty());
+        assert_eq!(
+            req.shortfalls(install, 3000 * MB, cache, 3000 * MB, true)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_check_passes_for_small_plan() {
+        let dir = std::env::temp_dir().join("cudamgr_space_check");
+        let mut small = plan(&[Some(MB)], None);
+        small.install_path = dir.join("versions/12.4.1");
+        assert!(check(&small, &dir.join("cache")).is_ok());
+
+        let mut huge = plan(&[Some(u64::MAX / 8)], None);
+        huge.install_path = dir.join("versions/12.4.1");
+        let err = check(&huge, &dir.join("cache")).unwrap_err().to_string();
+        assert!(err.contains("Not enough disk space for CUDA 12.4.1"));
+    }
+}
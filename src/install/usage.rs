@@ -0,0 +1,115 @@
+//! Disk usage of installed toolkits.
+//!
+//! Trees are walked without following symlinks, so a library symlink costs only the link.
+//! After `cudamgr dedupe`, versions share files through hardlinks: each tree is charged for
+//! every inode it contains, the inodes it has in common with another tree are reported as
+//! shared, and the total on disk counts every inode once.
+
+use crate::error::CudaMgrResult;
+use crate::system::cuda::{file_id, CudaInstallation};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Usage of one measured directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeUsage {
+    pub label: String,
+    pub path: PathBuf,
+    /// Bytes of every file in the tree, hardlinks inside it counted once
+    pub bytes: u64,
+    /// Part of `bytes` held in files hardlinked into another measured tree
+    pub shared_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UsageReport {
+    pub trees: Vec<TreeUsage>,
+    /// Bytes actually used on disk by all trees together
+    pub on_disk_bytes: u64,
+}
+
+impl UsageReport {
+    /// Bytes hardlinks save compared to every tree holding its own copies.
+    pub fn saved_bytes(&self) -> u64 {
+        let apparent: u64 = self.trees.iter().map(|t| t.bytes).sum();
+        apparent.saturating_sub(self.on_disk_bytes)
+    }
+}
+
+/// Measure each `(label, path)` tree. Missing directories measure as empty.
+pub fn measure(trees: &[(String, PathBuf)]) -> CudaMgrResult<UsageReport> {
+    let mut report = UsageReport::default();
+    // inode -> (size, indices of the trees containing it)
+    let mut inodes: HashMap<(u64, u64), (u64, Vec<usize>)> = HashMap::new();
+    for (index, (label, path)) in trees.iter().enumerate() {
+        let mut tree = TreeUsage {
+            label: label.clone(),
+            path: path.clone(),
+            bytes: 0,
+            shared_bytes: 0,
+        };
+        CudaInstallation::visit_files(path, &mut |_, meta| match file_id(meta) {
+            Some(id) => {
+                let (_, owners) = inodes.entry(id).or_insert((meta.len(), Vec::new()));
+                if !owners.contains(&index) {
+                    owners.push(index);
+                    tree.bytes += meta.len();
+                }
+            }
+            None => {
+                tree.bytes += meta.len();
+                report.on_disk_bytes += meta.len();
+            }
+        })?;
+        report.trees.push(tree);
+    }
+    for (size, owners) in inodes.into_values() {
+        report.on_disk_bytes += size;
+        if owners.len() > 1 {
+            for index in owners {
+                report.trees[index].shared_bytes += size;
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_counts_shared_inodes_once() {
+        let root = std::env::temp_dir().join("cudamgr_usage");
+        let _ = std::fs::remove_dir_all(&root);
+        let a = root.join("12.4.0");
+        let b = root.join("12.4.1");
+        for dir in [&a, &b] {
+            std::fs::create_dir_all(dir.join("include")).unwrap();
+        }
+        std::fs::write(a.join("include/cuda.h"), "0123456789").unwrap();
+        std::fs::write(a.join("include/own.h"), "abc").unwrap();
+        std::fs::write(b.join("include/own.h"), "abcdef").unwrap();
+        std::fs::hard_link(a.join("include/cuda.h"), b.join("include/cuda.h")).unwrap();
+        // A second link inside the same tree is still one file
+        std::fs::hard_link(a.join("include/cuda.h"), a.join("include/copy.h")).unwrap();
+
+        let report = measure(&[
+            ("12.4.0".to_string(), a.clone()),
+            ("12.4.1".to_string(), b.clone()),
+            ("gone".to_string(), root.join("missing")),
+        ])
+        .unwrap();
+        assert_eq!(report.trees[1].bytes, 16);
+        assert_eq!(report.trees[2].bytes, 0);
+        if cfg!(unix) {
+            assert_eq!(report.trees[0].bytes, 13);
+            assert_eq!(report.trees[0].shared_bytes, 10);
+            assert_eq!(report.trees[1].shared_bytes, 10);
+            assert_eq!(report.on_disk_bytes, 19);
+            assert_eq!(report.saved_bytes(), 10);
+            assert_eq!(CudaInstallation::calculate_install_size(&a).unwrap(), 13);
+        }
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -1,5 +1,68 @@
-use crate::error::{CudaMgrResult, InstallError};
-use std::path::Path;
+//! Post-install checks for a toolkit directory.
+//!
+//! An install is checked against its receipt (every component placed files, every file still
+//! has its recorded size and sha256), then `nvcc --version` must report the expected release
+//! and a trivial kernel must compile with `nvcc -c` using the detected host compiler.
+
+use crate::error::CudaMgrResult;
+use crate::install::receipt::InstallReceipt;
+use crate::system::compiler::CompilerInfo;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Kernel compiled by the compile check.
+const PROBE_SOURCE: &str = "__global__ void cudamgr_probe(int *out) { out[threadIdx.x] = 1; }\n";
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// The check doesn't apply (e.g. nvcc wasn't part of the install)
+    Skip,
+}
+
+impl CheckStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Fail => "FAIL",
+            CheckStatus::Skip => "SKIP",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Every check run against one install.
+#[derive(Debug, Clone)]
+pub struct ValidationReport {
+    pub cuda_version: String,
+    pub install_path: PathBuf,
+    pub checks: Vec<Check>,
+}
+
+impl ValidationReport {
+    /// True when no check failed.
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.status != CheckStatus::Fail)
+    }
+}
 
 /// Installation validation utilities
 pub struct InstallationValidator;
@@ -10,31 +73,191 @@ impl InstallationValidator {
         Self
     }
 
+    /// Run every check against the install at `install_path`. Installs without a receipt
+    /// are checked by name: the directory name is taken as the version.
+    pub async fn validate(&self, install_path: &Path) -> CudaMgrResult<ValidationReport> {
+        tracing::info!("Verifying CUDA installation at {:?}", install_path);
+        let receipt = InstallReceipt::load(install_path)?;
+        let cuda_version = match &receipt {
+            Some(r) => r.cuda_version.clone(),
+            None => install_path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+
+        let mut checks = vec![
+            Self::check_components(install_path, receipt.as_ref()),
+            Self::check_files(install_path, receipt.as_ref()),
+        ];
+        let has_nvcc = receipt
+            .as_ref()
+            .map(|r| r.components.iter().any(|c| c.name == "cuda_nvcc"))
+            .unwrap_or(true);
+        if has_nvcc {
+            checks.push(self.check_nvcc_version(install_path, &cuda_version));
+            checks.push(self.test_nvcc(install_path));
+        } else {
+            let detail = "cuda_nvcc is not installed";
+            checks.push(Check::new("nvcc version", CheckStatus::Skip, detail));
+            checks.push(Check::new("compile", CheckStatus::Skip, detail));
+        }
+
+        Ok(ValidationReport {
+            cuda_version,
+            install_path: install_path.to_path_buf(),
+            checks,
+        })
+    }
+
     /// Verify CUDA installation is complete and functional
     pub async fn verify_installation(&self, install_path: &Path) -> CudaMgrResult<bool> {
-        // TODO: Implement installation verification
-        tracing::info!("Verifying CUDA installation at {:?}", install_path);
-        Err(
-            InstallError::Validation("Installation verification not yet implemented".to_string())
-                .into(),
-        )
+        Ok(self.validate(install_path).await?.passed())
+    }
+
+    /// Every component in the receipt placed at least one file, and those files exist.
+    fn check_components(install_path: &Path, receipt: Option<&InstallReceipt>) -> Check {
+        let name = "components";
+        let Some(receipt) = receipt else {
+            return Check::new(name, CheckStatus::Skip, "no install receipt");
+        };
+        let missing: Vec<&str> = receipt
+            .components
+            .iter()
+            .filter(|c| {
+                !receipt
+                    .files
+                    .iter()
+                    .any(|f| f.component == c.name && install_path.join(&f.path).is_file())
+            })
+            .map(|c| c.name.as_str())
+            .collect();
+        if missing.is_empty() {
+            Check::new(
+                name,
+                CheckStatus::Pass,
+                format!("{} components present", receipt.components.len()),
+            )
+        } else {
+            Check::new(
+                name,
+                CheckStatus::Fail,
+                format!("missing: {}", missing.join(", ")),
+            )
+        }
     }
 
-    /// Run post-installation tests
-    pub async fn run_post_install_tests(&self, install_path: &Path) -> CudaMgrResult<Vec<String>> {
-        // TODO: Implement post-installation testing
-        tracing::info!("Running post-installation tests for {:?}", install_path);
-        Err(
-            InstallError::Validation("Post-installation tests not yet implemented".to_string())
-                .into(),
-        )
+    /// Every file in the receipt still has its recorded size and sha256.
+    fn check_files(install_path: &Path, receipt: Option<&InstallReceipt>) -> Check {
+        let name = "file hashes";
+        let Some(receipt) = receipt else {
+            return Check::new(name, CheckStatus::Skip, "no install receipt");
+        };
+        let bad: Vec<&str> = receipt
+            .damaged_files(install_path)
+            .into_iter()
+            .map(|f| f.path.as_str())
+            .collect();
+        match bad.as_slice() {
+            [] => Check::new(
+                name,
+                CheckStatus::Pass,
+                format!("{} files match", receipt.files.len()),
+            ),
+            [only] => Check::new(name, CheckStatus::Fail, format!("{} changed", only)),
+            [first, rest @ ..] => Check::new(
+                name,
+                CheckStatus::Fail,
+                format!("{} and {} other files changed", first, rest.len()),
+            ),
+        }
     }
 
-    /// Check if CUDA compiler is working
-    pub async fn test_nvcc(&self, install_path: &Path) -> CudaMgrResult<bool> {
-        // TODO: Implement nvcc testing
-        tracing::info!("Testing nvcc at {:?}", install_path);
-        Err(InstallError::Validation("nvcc testing not yet implemented".to_string()).into())
+    /// `nvcc --version` reports the release of `cuda_version`.
+    fn check_nvcc_version(&self, install_path: &Path, cuda_version: &str) -> Check {
+        let name = "nvcc version";
+        let nvcc = super::nvcc_binary_path(install_path);
+        let output = match Command::new(&nvcc).arg("--version").output() {
+            Ok(output) if output.status.success() => output,
+            Ok(output) => {
+                return Check::new(
+                    name,
+                    CheckStatus::Fail,
+                    format!("nvcc --version exited with {}", output.status),
+                )
+            }
+            Err(e) => {
+                return Check::new(
+                    name,
+                    CheckStatus::Fail,
+                    format!("cannot run {}: {}", nvcc.display(), e),
+                )
+            }
+        };
+        let expected: Vec<&str> = cuda_version.split('.').take(2).collect();
+        let expected = expected.join(".");
+        match parse_nvcc_release(&String::from_utf8_lossy(&output.stdout)) {
+            Some(release) if release == expected => {
+                Check::new(name, CheckStatus::Pass, format!("release {}", release))
+            }
+            Some(release) => Check::new(
+                name,
+                CheckStatus::Fail,
+                format!("reports release {}, expected {}", release, expected),
+            ),
+            None => Check::new(name, CheckStatus::Fail, "no release in nvcc --version"),
+        }
+    }
+
+    /// Check if CUDA compiler is working: compile a trivial kernel with `nvcc -c`.
+    fn test_nvcc(&self, install_path: &Path) -> Check {
+        let name = "compile";
+        let nvcc = super::nvcc_binary_path(install_path);
+        if !nvcc.is_file() {
+            return Check::new(name, CheckStatus::Fail, "nvcc is missing");
+        }
+        let host = CompilerInfo::detect()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|c| c.is_compatible);
+
+        let dir = std::env::temp_dir().join(format!("cudamgr_verify_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        if let Err(e) = std::fs::create_dir_all(&dir)
+            .and_then(|()| std::fs::write(dir.join("probe.cu"), PROBE_SOURCE))
+        {
+            return Check::new(name, CheckStatus::Fail, format!("temp dir: {}", e));
+        }
+        let mut cmd = Command::new(&nvcc);
+        cmd.current_dir(&dir)
+            .args(["-c", "probe.cu", "-o", "probe.o"]);
+        if let Some(path) = host.as_ref().and_then(|c| c.path.as_deref()) {
+            cmd.arg("-ccbin").arg(path);
+        }
+        let result = cmd.output();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let host_name = host
+            .as_ref()
+            .map(|c| format!("{} {}", c.name, c.version))
+            .unwrap_or_else(|| "nvcc's default host compiler".to_string());
+        match result {
+            Ok(output) if output.status.success() => Check::new(
+                name,
+                CheckStatus::Pass,
+                format!("nvcc -c with {}", host_name),
+            ),
+            Ok(output) => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let first = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+                Check::new(
+                    name,
+                    CheckStatus::Fail,
+                    format!("nvcc -c with {} failed: {}", host_name, first.trim()),
+                )
+            }
+            Err(e) => Check::new(name, CheckStatus::Fail, format!("cannot run nvcc: {}", e)),
+        }
     }
 }
 
@@ -43,3 +266,58 @@ impl Default for InstallationValidator {
         Self::new()
     }
 }
+
+/// The `X.Y` in nvcc's "Cuda compilation tools, release X.Y, VX.Y.Z" line.
+fn parse_nvcc_release(output: &str) -> Option<String> {
+    let rest = output.split("release ").nth(1)?;
+    let release: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == '.')
+        .collect();
+    (!release.is_empty()).then_some(release)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nvcc_release() {
+        let output = "nvcc: NVIDIA (R) Cuda compiler driver\n\
+                      Copyright (c) 2005-2024 NVIDIA Corporation\n\
+                      Cuda compilation tools, release 12.4, V12.4.131\n";
+        assert_eq!(parse_nvcc_release(output), Some("12.4".to_string()));
+        assert_eq!(parse_nvcc_release("command not found"), None);
+    }
+
+    #[tokio::test]
+    async fn test_validate_detects_changed_files() {
+        let root = std::env::temp_dir().join("cudamgr_validator");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("include")).unwrap();
+        std::fs::write(root.join("include/cuda.h"), "header").unwrap();
+        std::fs::write(root.join("include/cufft.h"), "fft").unwrap();
+        let placed = vec![
+            ("cuda_cudart".to_string(), PathBuf::from("include/cuda.h")),
+            ("libcufft".to_string(), PathBuf::from("include/cufft.h")),
+        ];
+        InstallReceipt::record("12.4.1", None, Vec::new(), &root, &placed)
+            .unwrap()
+            .write(&root)
+            .unwrap();
+
+        let validator = InstallationValidator::new();
+        let report = validator.validate(&root).await.unwrap();
+        assert_eq!(report.cuda_version, "12.4.1");
+        assert!(report.passed(), "{:?}", report.checks);
+        assert_eq!(report.checks[2].status, CheckStatus::Skip);
+
+        std::fs::write(root.join("include/cuda.h"), "HEADER").unwrap();
+        std::fs::remove_file(root.join("include/cufft.h")).unwrap();
+        let report = validator.validate(&root).await.unwrap();
+        assert!(!report.passed());
+        assert_eq!(report.checks[0].detail, "missing: libcufft");
+        assert_eq!(report.checks[1].status, CheckStatus::Fail);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
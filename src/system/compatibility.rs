@@ -1,3 +1,4 @@
+use crate::install::retry::{self, Failure};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -98,22 +99,28 @@ impl CompatibilityRegistry {
 
     /// Load from remote URL (non-blocking, returns Result)
     pub async fn load_from_url(url: &str) -> Result<Self, RegistryError> {
-        let response = reqwest::get(url)
-            .await
-            .map_err(|e| RegistryError::Network(e.to_string()))?;
-
-        if !response.status().is_success() {
-            return Err(RegistryError::Network(format!(
-                "HTTP {} from {}",
-                response.status(),
-                url
-            )));
-        }
-
-        let body = response
-            .text()
-            .await
+        let config = crate::config::CudaMgrConfig::load().ok();
+        let client = crate::install::http::client(config.as_ref())
             .map_err(|e| RegistryError::Network(e.to_string()))?;
+        let policy = config.map(|c| c.network.retry).unwrap_or_default();
+
+        let what = format!("Fetch of {}", url);
+        let body = retry::run(&policy, &what, |_| async {
+            let response = client
+                .get(url)
+                .send()
+                .await
+                .map_err(|e| Failure::from_reqwest("Request failed", &e))?;
+            if !response.status().is_success() {
+                return Err(Failure::from_status(&policy, &response));
+            }
+            response
+                .text()
+                .await
+                .map_err(|e| Failure::from_reqwest("Read body", &e))
+        })
+        .await
+        .map_err(|e| RegistryError::Network(e.to_string()))?;
 
         Self::load_from_str(&body)
     }
@@ -313,6 +320,17 @@ impl CompatibilityRegistry {
             .collect()
     }
 
+    /// Minimum driver branch for a CUDA release, matched on major.minor ("12.4.1" -> "550").
+    pub fn min_driver_for(&self, cuda_version: &str) -> Option<String> {
+        let major_minor: Vec<&str> = cuda_version.split('.').take(2).collect();
+        let major_minor = major_minor.join(".");
+        self.driver_cuda_map
+            .iter()
+            .rev()
+            .find(|(_, cuda)| *cuda == major_minor)
+            .map(|(driver, _)| driver.clone())
+    }
+
     // ── Built-in defaults ────────────────────────────────────────────
 
     fn default_builtin() -> Self {
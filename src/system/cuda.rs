@@ -1,6 +1,7 @@
 use crate::error::{CudaMgrResult, SystemError};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -208,7 +209,7 @@ impl CudaInstallation {
     }
 
     /// Calculate total size of installation directory
-    fn calculate_directory_size(path: &Path) -> CudaMgrResult<u64> {
+    pub fn calculate_directory_size(path: &Path) -> CudaMgrResult<u64> {
         fn dir_size(path: &Path) -> std::io::Result<u64> {
             let mut size = 0;
             if path.is_dir() {
@@ -228,6 +229,46 @@ impl CudaInstallation {
         dir_size(path).map_err(|e| SystemError::Io(e).into())
     }
 
+    /// Size of an install as it sits on disk. Unlike [`Self::calculate_directory_size`],
+    /// symlinks are counted as links instead of being followed, and a file hardlinked
+    /// several times inside `path` is counted once.
+    pub fn calculate_install_size(path: &Path) -> CudaMgrResult<u64> {
+        let mut seen = HashSet::new();
+        let mut size = 0;
+        Self::visit_files(path, &mut |_, meta| {
+            if file_id(meta).is_none_or(|id| seen.insert(id)) {
+                size += meta.len();
+            }
+        })?;
+        Ok(size)
+    }
+
+    /// Call `visit` for every entry under `path` that isn't a directory, without following
+    /// symlinks.
+    pub fn visit_files(
+        path: &Path,
+        visit: &mut dyn FnMut(&Path, &fs::Metadata),
+    ) -> CudaMgrResult<()> {
+        fn walk(path: &Path, visit: &mut dyn FnMut(&Path, &fs::Metadata)) -> std::io::Result<()> {
+            for entry in fs::read_dir(path)? {
+                let entry = entry?;
+                let path = entry.path();
+                let meta = fs::symlink_metadata(&path)?;
+                if meta.is_dir() {
+                    walk(&path, visit)?;
+                } else {
+                    visit(&path, &meta);
+                }
+            }
+            Ok(())
+        }
+
+        if !fs::symlink_metadata(path).is_ok_and(|m| m.is_dir()) {
+            return Ok(());
+        }
+        walk(path, visit).map_err(|e| SystemError::Io(e).into())
+    }
+
     /// Detect all CUDA installations on the system
     pub fn detect_all_installations() -> CudaMgrResult<CudaDetectionResult> {
         let mut installations = Vec::new();
@@ -461,3 +502,15 @@ impl std::fmt::Display for CudaVersion {
         }
     }
 }
+
+/// (device, inode) of a file on Unix, which hardlinks share.
+#[cfg(unix)]
+pub fn file_id(meta: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+pub fn file_id(_meta: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
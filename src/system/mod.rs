@@ -71,7 +71,10 @@ impl SystemChecker for DefaultSystemChecker {
         let distro = distro::DistroInfo::detect()?;
 
         // Detect storage information
-        let storage_path = storage::StorageInfo::get_default_cuda_path();
+        // cudamgr installs under its own install_dir, not the system CUDA path
+        let storage_path = crate::config::CudaMgrConfig::load()
+            .map(|c| c.install_dir)
+            .unwrap_or_else(|_| storage::StorageInfo::get_default_cuda_path());
         let storage = storage::StorageInfo::detect(&storage_path)?;
 
         // Detect security information
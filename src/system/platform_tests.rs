@@ -1,4 +1,5 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod platform_tests {
     use crate::system::distro::*;
 
@@ -46,7 +46,10 @@ impl SystemReportGenerator {
         let distro = super::distro::DistroInfo::detect()?;
 
         // Detect storage information
-        let storage_path = super::storage::StorageInfo::get_default_cuda_path();
+        // cudamgr installs under its own install_dir, not the system CUDA path
+        let storage_path = crate::config::CudaMgrConfig::load()
+            .map(|c| c.install_dir)
+            .unwrap_or_else(|_| super::storage::StorageInfo::get_default_cuda_path());
         let storage = super::storage::StorageInfo::detect(&storage_path)?;
 
         // Detect security information
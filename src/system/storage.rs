@@ -32,7 +32,8 @@ impl StorageInfo {
         let path_str = install_path.to_string_lossy().to_string();
 
         // Get disk space information
-        let (available_bytes, total_bytes) = Self::get_disk_space(install_path)?;
+        let (available_bytes, total_bytes) =
+            Self::get_disk_space(&Self::nearest_existing(install_path))?;
 
         let available_gb = available_bytes / (1024 * 1024 * 1024);
         let total_gb = total_bytes / (1024 * 1024 * 1024);
@@ -59,6 +60,36 @@ impl StorageInfo {
         }
     }
 
+    /// Bytes free to unprivileged users on the filesystem that holds (or would hold) `path`
+    pub fn available_bytes(path: &Path) -> CudaMgrResult<u64> {
+        Ok(Self::get_disk_space(&Self::nearest_existing(path))?.0)
+    }
+
+    /// Whether two paths, existing or not yet created, end up on the same filesystem
+    pub fn same_filesystem(a: &Path, b: &Path) -> bool {
+        let (a, b) = (Self::nearest_existing(a), Self::nearest_existing(b));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            match (std::fs::metadata(&a), std::fs::metadata(&b)) {
+                (Ok(ma), Ok(mb)) => ma.dev() == mb.dev(),
+                _ => false,
+            }
+        }
+        #[cfg(windows)]
+        {
+            a.components().next() == b.components().next()
+        }
+    }
+
+    /// `path` itself or the closest ancestor that exists
+    fn nearest_existing(path: &Path) -> PathBuf {
+        path.ancestors()
+            .find(|p| !p.as_os_str().is_empty() && p.exists())
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
     /// Get disk space information for a path
     fn get_disk_space(path: &Path) -> CudaMgrResult<(u64, u64)> {
         #[cfg(unix)]
@@ -171,3 +202,19 @@ impl StorageInfo {
         )
     }
 }
+
+/// Format a byte count for humans (e.g. "3.2 GB")
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
@@ -15,6 +15,25 @@ pub struct VersionInfo {
     pub is_active: bool,
     pub install_date: DateTime<Utc>,
     pub size_bytes: u64,
+    /// Redist components installed (empty for entries recorded before component tracking)
+    #[serde(default)]
+    pub components: Vec<String>,
+    /// Companion libraries (cuDNN, NCCL, ...) added for this toolkit
+    #[serde(default)]
+    pub companions: Vec<CompanionInfo>,
+}
+
+/// A companion library installed against a toolkit with `cudamgr add`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompanionInfo {
+    /// Library key (e.g. "cudnn")
+    pub name: String,
+    /// Full library version (e.g. "9.1.0")
+    pub version: String,
+    /// Manifest variant installed (e.g. "cuda12")
+    pub cuda_variant: String,
+    /// The toolkit's own directory, or a sibling directory holding only the library
+    pub install_path: PathBuf,
 }
 
 /// Version manager trait
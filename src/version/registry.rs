@@ -184,6 +184,13 @@ mod tests {
             is_active: true,
             install_date: Utc::now(),
             size_bytes: 1_000_000,
+            components: vec!["cuda_nvcc".to_string()],
+            companions: vec![crate::version::CompanionInfo {
+                name: "cudnn".to_string(),
+                version: "9.1.0".to_string(),
+                cuda_variant: "cuda12".to_string(),
+                install_path: PathBuf::from("/opt/cuda/12.0.3"),
+            }],
         });
         reg.active_version = Some("12.0.3".to_string());
         reg.save().await.unwrap();
@@ -193,6 +200,8 @@ mod tests {
         assert_eq!(loaded.versions.len(), 1);
         assert_eq!(loaded.versions[0].version, "12.0.3");
         assert_eq!(loaded.active_version.as_deref(), Some("12.0.3"));
+        assert_eq!(loaded.versions[0].components, vec!["cuda_nvcc"]);
+        assert_eq!(loaded.versions[0].companions[0].version, "9.1.0");
 
         let _ = std::fs::remove_dir_all(&dir);
     }
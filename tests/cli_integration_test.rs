@@ -3,7 +3,7 @@ use std::process::Command;
 #[test]
 fn test_cli_help() {
     let output = Command::new("cargo")
-        .args(&["run", "--", "--help"])
+        .args(["run", "--", "--help"])
         .output()
         .expect("Failed to execute command");
 
@@ -21,7 +21,7 @@ fn test_cli_help() {
 #[test]
 fn test_doctor_help() {
     let output = Command::new("cargo")
-        .args(&["run", "--", "doctor", "--help"])
+        .args(["run", "--", "doctor", "--help"])
         .output()
         .expect("Failed to execute command");
 
@@ -34,7 +34,7 @@ fn test_doctor_help() {
 #[test]
 fn test_install_help() {
     let output = Command::new("cargo")
-        .args(&["run", "--", "install", "--help"])
+        .args(["run", "--", "install", "--help"])
         .output()
         .expect("Failed to execute command");
 
@@ -48,7 +48,7 @@ fn test_install_help() {
 #[test]
 fn test_doctor_command_execution() {
     let output = Command::new("cargo")
-        .args(&["run", "--", "doctor"])
+        .args(["run", "--", "doctor"])
         .output()
         .expect("Failed to execute command");
 